@@ -0,0 +1,131 @@
+//! Single-line inputs read as a stream of characters
+//!
+//! Editors usually save a trailing newline, so trailing whitespace is always
+//! dropped. Any other character a puzzle has no use for is a stray, handled
+//! as chosen with `stray=error|ignore|warn`.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+
+use super::Options;
+
+/// What to do with a character the puzzle does not use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stray {
+    /// Fail on the first one
+    Error,
+    /// Skip it silently
+    Ignore,
+    /// Skip it, logging where it was
+    Warn,
+}
+
+impl FromStr for Stray {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "error" => Ok(Stray::Error),
+            "ignore" => Ok(Stray::Ignore),
+            "warn" => Ok(Stray::Warn),
+            _ => anyhow::bail!("expected `error`, `ignore` or `warn`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CharStream<'a> {
+    text: &'a str,
+    stray: Stray,
+}
+
+impl<'a> CharStream<'a> {
+    pub fn new(input: &'a str) -> CharStream<'a> {
+        CharStream {
+            text: input.trim_end(),
+            stray: Stray::Error,
+        }
+    }
+
+    /// Options: `stray=error|ignore|warn` (default `error`)
+    pub fn from_options(input: &'a str, opts: &Options) -> Result<CharStream<'a>> {
+        let stream = CharStream::new(input);
+        Ok(match opts.get("stray")? {
+            Some(stray) => stream.stray(stray),
+            None => stream,
+        })
+    }
+
+    pub fn stray(self, stray: Stray) -> CharStream<'a> {
+        CharStream { stray, ..self }
+    }
+
+    /// Turn each character into a `T`, with `decode` returning `None` for strays
+    ///
+    /// `what` describes the characters the puzzle expects, for error messages.
+    pub fn decode<T, F>(&self, what: &str, decode: F) -> Result<Vec<T>>
+    where
+        F: Fn(char) -> Option<T>,
+    {
+        let mut out = Vec::with_capacity(self.text.len());
+        for (offset, c) in self.text.char_indices() {
+            match (decode(c), self.stray) {
+                (Some(t), _) => out.push(t),
+                (None, Stray::Error) => {
+                    anyhow::bail!("expected {} but found {:?} at byte {}", what, c, offset)
+                }
+                (None, Stray::Ignore) => {}
+                (None, Stray::Warn) => log::warn!("skipping {:?} at byte {}", c, offset),
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bit(c: char) -> Option<u8> {
+        match c {
+            '0' => Some(0),
+            '1' => Some(1),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        let s = CharStream::new("0110\r\n \n");
+        assert_eq!(s.decode("a bit", bit).unwrap(), vec![0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn strays() {
+        let input = "01 1\n0\n";
+        assert_eq!(
+            CharStream::new(input)
+                .decode("a bit", bit)
+                .unwrap_err()
+                .to_string(),
+            "expected a bit but found ' ' at byte 2"
+        );
+        for stray in &[Stray::Ignore, Stray::Warn] {
+            let s = CharStream::new(input).stray(*stray);
+            assert_eq!(s.decode("a bit", bit).unwrap(), vec![0, 1, 1, 0]);
+        }
+
+        let opts = Options::from_pairs(&[("stray", "ignore")]);
+        let s = CharStream::from_options("é1", &opts).unwrap();
+        assert_eq!(s.decode("a bit", bit).unwrap(), vec![1]);
+        assert_eq!(
+            CharStream::new("é1")
+                .decode("a bit", bit)
+                .unwrap_err()
+                .to_string(),
+            "expected a bit but found 'é' at byte 0"
+        );
+        let opts = Options::from_pairs(&[("stray", "skip")]);
+        assert!(CharStream::from_options("", &opts).is_err());
+    }
+}
@@ -1,34 +1,146 @@
-use anyhow::{anyhow as ah, Result};
-
-pub fn part1(input: &str) -> Result<String> {
-    let mut floor = 0;
-    for c in input.chars() {
-        match c {
-            '(' => floor += 1,
-            ')' => floor -= 1,
-            _ => return Err(ah!("unknown input char: {}", c)),
+use anyhow::{anyhow as ah, Context, Result};
+
+use super::{chars::CharStream, Options};
+
+mod plot;
+
+/// One floor up or down for each bracket
+fn parse_input(input: &str, opts: &Options) -> Result<Vec<i64>> {
+    CharStream::from_options(input, opts)?.decode("`(` or `)`", |c| match c {
+        '(' => Some(1),
+        ')' => Some(-1),
+        _ => None,
+    })
+}
+
+pub fn part1(input: &str, opts: &Options) -> Result<String> {
+    let trace = Trace::new(&parse_input(input, opts)?);
+    Ok(format!("{:?}", trace.last()))
+}
+
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    Ok(format!("{:?}", first_basement(&parse_input(input, opts)?)?))
+}
+
+fn first_basement(moves: &[i64]) -> Result<usize> {
+    Trace::new(moves)
+        .first_reaching(-1)
+        .ok_or_else(|| ah!("never entered basement"))
+}
+
+/// The floor after every instruction, starting from the ground floor
+#[derive(Debug, Clone, PartialEq)]
+struct Trace {
+    /// `floors[0]` is the ground floor before any instruction
+    floors: Vec<i64>,
+}
+
+impl Trace {
+    fn new(moves: &[i64]) -> Trace {
+        let mut floors = Vec::with_capacity(moves.len() + 1);
+        let mut floor = 0;
+        floors.push(floor);
+        for m in moves {
+            floor += m;
+            floors.push(floor);
         }
+        Trace { floors }
+    }
+
+    fn steps(&self) -> usize {
+        self.floors.len() - 1
+    }
+
+    fn last(&self) -> i64 {
+        *self
+            .floors
+            .last()
+            .expect("trace starts on the ground floor")
+    }
+
+    /// Lowest floor and the first step that reached it, 0 if it's the ground floor
+    fn lowest(&self) -> (i64, usize) {
+        self.first_by_key(|f| f)
+    }
+
+    /// Highest floor and the first step that reached it, 0 if it's the ground floor
+    fn highest(&self) -> (i64, usize) {
+        self.first_by_key(std::cmp::Reverse)
+    }
+
+    /// The floor that sorts first by `key`, and the earliest step it was on
+    fn first_by_key<K: Ord>(&self, key: impl Fn(i64) -> K) -> (i64, usize) {
+        let (step, floor) = self
+            .floors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, f)| key(**f))
+            .expect("trace starts on the ground floor");
+        (*floor, step)
+    }
+
+    /// The first step (counting from 1) after which Santa is on `floor`
+    fn first_reaching(&self, floor: i64) -> Option<usize> {
+        self.floors
+            .iter()
+            .skip(1)
+            .position(|f| *f == floor)
+            .map(|i| i + 1)
+    }
+
+    /// How many times Santa went down into the basement
+    fn basement_visits(&self) -> usize {
+        self.floors
+            .windows(2)
+            .filter(|w| w[0] >= 0 && w[1] < 0)
+            .count()
     }
-    Ok(format!("{:?}", floor))
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    Ok(format!("{:?}", first_basement(input)?))
+/// Comma separated floors, e.g. `floors=-1,10`
+fn parse_floors(list: &str) -> Result<Vec<i64>> {
+    list.split(',')
+        .map(|s| {
+            s.trim()
+                .parse::<i64>()
+                .with_context(|| format!("invalid floor {:?}", s))
+        })
+        .collect()
 }
 
-fn first_basement(input: &str) -> Result<usize> {
-    let mut floor = 0;
-    for (idx, c) in input.chars().enumerate() {
-        match c {
-            '(' => floor += 1,
-            ')' => floor -= 1,
-            _ => return Err(ah!("unknown input char: {}", c)),
-        }
-        if floor == -1 {
-            return Ok(idx + 1);
-        }
+/// Statistics about the whole walk, with a plot of floor against step
+///
+/// Options: `floors=F,F,...` to find the first visit to (default `-1`), and
+/// `width=N`/`height=N` for the plot (default 72x16, `height=0` for none)
+pub fn trace(input: &str, opts: &Options) -> Result<String> {
+    let trace = Trace::new(&parse_input(input, opts)?);
+    let floors = match opts.get_str("floors") {
+        Some(list) => parse_floors(list)?,
+        None => vec![-1],
+    };
+    let width = opts.get("width")?.unwrap_or(72);
+    let height = opts.get("height")?.unwrap_or(16);
+
+    let (low, low_step) = trace.lowest();
+    let (high, high_step) = trace.highest();
+    let mut lines = vec![
+        format!("steps: {}", trace.steps()),
+        format!("final floor: {}", trace.last()),
+        format!("lowest floor: {} (step {})", low, low_step),
+        format!("highest floor: {} (step {})", high, high_step),
+        format!("basement visits: {}", trace.basement_visits()),
+    ];
+    for floor in floors {
+        lines.push(match trace.first_reaching(floor) {
+            Some(step) => format!("first reached floor {} at step {}", floor, step),
+            None => format!("never reached floor {}", floor),
+        });
     }
-    Err(ah!("never entered basement"))
+    if height > 0 {
+        lines.push(String::new());
+        lines.push(plot::plot(&trace.floors, width, height)?);
+    }
+    Ok(lines.join("\n"))
 }
 
 #[cfg(test)]
@@ -38,22 +150,92 @@ mod tests {
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "232")
+        assert_eq!(part1(INPUT, &Options::default()).unwrap().as_str(), "232")
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "1783")
+        assert_eq!(part2(INPUT, &Options::default()).unwrap().as_str(), "1783")
     }
 
     #[test]
     fn p1_basics() {
-        assert_eq!(part1("(())").unwrap().as_str(), "0");
-        assert_eq!(part1("()()").unwrap().as_str(), "0");
-        assert_eq!(part1("(((").unwrap().as_str(), "3");
-        assert_eq!(part1("(()(()(").unwrap().as_str(), "3");
-        assert_eq!(part1("))(((((").unwrap().as_str(), "3");
-        assert_eq!(part1("())").unwrap().as_str(), "-1");
-        assert_eq!(part1(")))").unwrap().as_str(), "-3");
-        assert_eq!(part1(")())())").unwrap().as_str(), "-3");
+        assert_eq!(part1("(())", &Options::default()).unwrap().as_str(), "0");
+        assert_eq!(part1("()()", &Options::default()).unwrap().as_str(), "0");
+        assert_eq!(part1("(((", &Options::default()).unwrap().as_str(), "3");
+        assert_eq!(part1("(()(()(", &Options::default()).unwrap().as_str(), "3");
+        assert_eq!(part1("))(((((", &Options::default()).unwrap().as_str(), "3");
+        assert_eq!(part1("())", &Options::default()).unwrap().as_str(), "-1");
+        assert_eq!(part1(")))", &Options::default()).unwrap().as_str(), "-3");
+        assert_eq!(
+            part1(")())())", &Options::default()).unwrap().as_str(),
+            "-3"
+        );
+    }
+
+    #[test]
+    fn p2_basics() {
+        assert_eq!(part2(")", &Options::default()).unwrap().as_str(), "1");
+        assert_eq!(part2("()())", &Options::default()).unwrap().as_str(), "5");
+        assert!(part2("(((", &Options::default()).is_err());
+    }
+
+    fn trace_of(input: &str) -> Trace {
+        Trace::new(&parse_input(input, &Options::default()).unwrap())
+    }
+
+    #[test]
+    fn trace_stats() {
+        let t = trace_of("))((((()))))))(");
+        assert_eq!(t.steps(), 15);
+        assert_eq!(t.last(), -3);
+        assert_eq!(t.lowest(), (-4, 14));
+        assert_eq!(t.highest(), (3, 7));
+        assert_eq!(t.first_reaching(0), Some(4));
+        assert_eq!(t.first_reaching(4), None);
+        assert_eq!(t.basement_visits(), 2);
+
+        // the ground floor counts as step 0
+        assert_eq!(trace_of("()").lowest(), (0, 0));
+        assert_eq!(trace_of("()").highest(), (1, 1));
+        assert_eq!(trace_of("((").lowest(), (0, 0));
+        assert_eq!(trace_of("))(((").highest(), (1, 5));
+        assert_eq!(trace_of(")((").highest(), (1, 3));
+        assert_eq!(trace_of("()()").lowest(), (0, 0));
+
+        let empty = trace_of("");
+        assert_eq!(
+            (empty.last(), empty.lowest(), empty.basement_visits()),
+            (0, (0, 0), 0)
+        );
+    }
+
+    #[test]
+    fn saved_by_an_editor() {
+        let none = Options::default();
+        assert_eq!(part1("(()\n", &none).unwrap().as_str(), "1");
+        assert_eq!(
+            part1("(x)", &none).unwrap_err().to_string(),
+            "expected `(` or `)` but found 'x' at byte 1"
+        );
+        let ignore = Options::from_pairs(&[("stray", "ignore")]);
+        assert_eq!(part2("( )\n)", &ignore).unwrap().as_str(), "3");
+    }
+
+    #[test]
+    fn trace_report() {
+        let opts = Options::from_pairs(&[("floors", "-1,2,5"), ("height", "0")]);
+        assert_eq!(
+            trace("))((((()))))))(", &opts).unwrap(),
+            "steps: 15\n\
+             final floor: -3\n\
+             lowest floor: -4 (step 14)\n\
+             highest floor: 3 (step 7)\n\
+             basement visits: 2\n\
+             first reached floor -1 at step 1\n\
+             first reached floor 2 at step 6\n\
+             never reached floor 5"
+        );
+        let opts = Options::from_pairs(&[("floors", "up")]);
+        assert!(trace("()", &opts).is_err());
     }
 }
@@ -0,0 +1,92 @@
+//! ASCII chart of floor against step
+//!
+//! Each column covers an equal share of the steps and is filled between the
+//! lowest and highest floor seen in that share. The ground floor is drawn as an
+//! axis wherever nothing else is.
+
+use anyhow::Result;
+
+pub fn plot(floors: &[i64], width: usize, height: usize) -> Result<String> {
+    if width == 0 || height == 0 {
+        anyhow::bail!("plot needs a width and height of at least 1");
+    }
+    let lo = std::cmp::min(0, floors.iter().cloned().min().unwrap_or(0));
+    let hi = std::cmp::max(0, floors.iter().cloned().max().unwrap_or(0));
+    let height = std::cmp::min(height as i64, hi - lo + 1) as usize;
+    let width = std::cmp::max(1, std::cmp::min(width, floors.len()));
+
+    // row 0 is the top of the chart
+    let row = |floor: i64| match hi - lo {
+        0 => 0,
+        span => ((hi - floor) * (height as i64 - 1) + span / 2) / span,
+    } as usize;
+
+    let mut grid = vec![vec![' '; width]; height];
+    for (col, cells) in (0..width).map(|c| (c, column(floors, c, width))) {
+        if let Some((min, max)) = cells {
+            for line in grid.iter_mut().take(row(min) + 1).skip(row(max)) {
+                line[col] = '#';
+            }
+        }
+    }
+    for cell in grid[row(0)].iter_mut() {
+        if *cell == ' ' {
+            *cell = '-';
+        }
+    }
+
+    let label_width = std::cmp::max(hi.to_string().len(), lo.to_string().len());
+    let lines = grid
+        .iter()
+        .enumerate()
+        .map(|(r, cells)| {
+            let label = if r == 0 {
+                hi.to_string()
+            } else if r == height - 1 {
+                lo.to_string()
+            } else if r == row(0) {
+                "0".to_string()
+            } else {
+                String::new()
+            };
+            let cells = cells.iter().collect::<String>();
+            format!("{:>w$} |{}", label, cells.trim_end(), w = label_width)
+        })
+        .collect::<Vec<_>>();
+    Ok(lines.join("\n"))
+}
+
+/// Lowest and highest floor in the share of steps drawn in column `col`
+fn column(floors: &[i64], col: usize, width: usize) -> Option<(i64, i64)> {
+    let start = col * floors.len() / width;
+    let end = (col + 1) * floors.len() / width;
+    let share = &floors[start..end];
+    Some((*share.iter().min()?, *share.iter().max()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_walk() {
+        let floors = [0, 1, 2, 1, 0, -1, -2, -1];
+        assert_eq!(
+            plot(&floors, 8, 5).unwrap(),
+            " 2 |  #\n   | # #\n 0 |#---#---\n   |     # #\n-2 |      #"
+        );
+        // two steps per column
+        assert_eq!(
+            plot(&floors, 4, 5).unwrap(),
+            " 2 | #\n   |##\n 0 |#-#-\n   |  ##\n-2 |   #"
+        );
+    }
+
+    #[test]
+    fn squashed() {
+        let floors = (0..=100).collect::<Vec<i64>>();
+        let chart = plot(&floors, 10, 3).unwrap();
+        assert_eq!(chart, "100 |       ###\n    |  ######\n  0 |###-------");
+        assert!(plot(&floors, 0, 3).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use anyhow::Result;
 
+use super::{chars::CharStream, Options};
+
 mod look_and_say {
     pub fn look_and_say<I: Iterator<Item = u32>>(
         input: I,
@@ -132,25 +134,18 @@ mod look_and_say {
     }
 }
 
-fn parse_input_as_ints(input: &str) -> Result<Vec<u32>> {
-    input
-        .trim()
-        .chars()
-        .map(|c| {
-            c.to_digit(10)
-                .ok_or_else(|| anyhow::anyhow!("char {:?} was not a base-10 digit", c))
-        })
-        .collect()
+fn parse_input_as_ints(input: &str, opts: &Options) -> Result<Vec<u32>> {
+    CharStream::from_options(input, opts)?.decode("a base-10 digit", |c| c.to_digit(10))
 }
 
-pub fn part1(input: &str) -> Result<String> {
-    let digits = parse_input_as_ints(input)?;
+pub fn part1(input: &str, opts: &Options) -> Result<String> {
+    let digits = parse_input_as_ints(input, opts)?;
     let x = look_and_say::look_and_say(digits.into_iter(), 40).count();
     Ok(format!("{:?}", x))
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let digits = parse_input_as_ints(input)?;
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    let digits = parse_input_as_ints(input, opts)?;
     let x = look_and_say::look_and_say(digits.into_iter(), 50).count();
     Ok(format!("{:?}", x))
 }
@@ -162,10 +157,16 @@ mod tests {
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "252594")
+        assert_eq!(
+            part1(INPUT, &Options::default()).unwrap().as_str(),
+            "252594"
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "3579328")
+        assert_eq!(
+            part2(INPUT, &Options::default()).unwrap().as_str(),
+            "3579328"
+        )
     }
 }
@@ -1,5 +1,7 @@
 use anyhow::{anyhow, Result};
 
+use super::{chars::CharStream, Options};
+
 fn next_letter(c: char) -> char {
     // Igore hij klm nop
     match c {
@@ -114,14 +116,21 @@ fn next_valid_password(start: &Password) -> Password {
     new
 }
 
-pub fn part1(input: &str) -> Result<String> {
-    let pw = Password::new(input)?;
+fn parse_input(input: &str, opts: &Options) -> Result<Password> {
+    let letters = CharStream::from_options(input, opts)?.decode("a lowercase letter", |c| {
+        Some(c).filter(char::is_ascii_lowercase)
+    })?;
+    Password::new(&letters.into_iter().collect::<String>())
+}
+
+pub fn part1(input: &str, opts: &Options) -> Result<String> {
+    let pw = parse_input(input, opts)?;
     let next = next_valid_password(&pw);
     Ok(format!("{}", next))
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let pw = Password::new(input)?;
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    let pw = parse_input(input, opts)?;
     let next = next_valid_password(&pw);
     let next2 = next_valid_password(&next);
     Ok(format!("{}", next2))
@@ -134,11 +143,17 @@ mod tests {
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "vzbxxyzz")
+        assert_eq!(
+            part1(INPUT, &Options::default()).unwrap().as_str(),
+            "vzbxxyzz"
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "vzcaabcc")
+        assert_eq!(
+            part2(INPUT, &Options::default()).unwrap().as_str(),
+            "vzcaabcc"
+        )
     }
 
     #[test]
@@ -147,6 +162,18 @@ mod tests {
         assert_eq!(pw.0, ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'])
     }
 
+    #[test]
+    fn stray_characters() {
+        assert_eq!(
+            part1("abcd efgh\n", &Options::default())
+                .unwrap_err()
+                .to_string(),
+            "expected a lowercase letter but found ' ' at byte 4"
+        );
+        let ignore = Options::from_pairs(&[("stray", "ignore")]);
+        assert_eq!(part1("abcd efgh\n", &ignore).unwrap().as_str(), "abcdffaa");
+    }
+
     #[test]
     fn password_too_short() {
         assert!(Password::new("abcdefg").is_err());
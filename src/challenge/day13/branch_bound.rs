@@ -0,0 +1,337 @@
+//! Depth-first search over seatings that skips rotations, mirror images, and
+//! any partial plan that can no longer beat the best one found so far.
+//!
+//! Guests are seated one table at a time. Round tables always start with the
+//! lowest unseated guest, which removes rotations and reorderings of the
+//! tables. A straight table has no rotations, so it only has to contain that
+//! guest somewhere. Mirror images are removed by requiring a round table's
+//! second guest to have a lower id than its last, and a straight table's first
+//! guest to have a lower id than its last. The bound works in doubled units:
+//! every remaining pairing is counted once from each end, and no guest can do
+//! better than their best (or best two) neighbors.
+
+use anyhow::Result;
+
+use crate::challenge::{
+    graph::{self, UpperTriangular},
+    progress::Progress,
+};
+
+/// How guests sit at a table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// The first and last guest are neighbors
+    Round,
+    /// A head-of-table row, where the two ends only have one neighbor
+    Linear,
+}
+
+impl std::str::FromStr for Shape {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "round" => Ok(Shape::Round),
+            "linear" => Ok(Shape::Linear),
+            _ => anyhow::bail!("expected `round` or `linear`"),
+        }
+    }
+}
+
+/// Restrictions on how guests may be seated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    /// Every table must have at least one guest
+    pub tables: usize,
+    pub shape: Shape,
+    /// Pairs that must sit next to each other
+    pub together: Vec<(usize, usize)>,
+    /// Pairs that must not sit next to each other
+    pub apart: Vec<(usize, usize)>,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            tables: 1,
+            shape: Shape::Round,
+            together: Vec::new(),
+            apart: Vec::new(),
+        }
+    }
+}
+
+/// Guest ids in seat order at each table, with the total happiness
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub tables: Vec<Vec<usize>>,
+    pub happiness: i32,
+}
+
+/// The plan with the highest total happiness that follows `rules`
+///
+/// `pairs` holds the combined happiness of two guests sitting together.
+pub fn best_plan(
+    pairs: &UpperTriangular<i32>,
+    rules: &Rules,
+    progress: &Progress,
+) -> Result<Option<Plan>> {
+    let n = pairs.size();
+    if rules.tables == 0 {
+        anyhow::bail!("there must be at least one table");
+    }
+    if n < rules.tables {
+        return Ok(None);
+    }
+
+    let mut top1 = vec![0; n];
+    let mut top2 = vec![0; n];
+    for guest in 0..n {
+        let mut best = (0..n)
+            .filter(|other| *other != guest)
+            .map(|other| pairs.get(guest, other))
+            .collect::<Vec<_>>();
+        best.sort_unstable_by(|a, b| b.cmp(a));
+        top1[guest] = best.first().cloned().unwrap_or(0);
+        top2[guest] = best.iter().take(2).sum();
+    }
+
+    let mut partners = vec![Vec::new(); n];
+    for (a, b) in &rules.together {
+        partners[*a].push(*b);
+        partners[*b].push(*a);
+    }
+    let mut forbidden = vec![false; n * n];
+    for (a, b) in &rules.apart {
+        forbidden[a * n + b] = true;
+        forbidden[b * n + a] = true;
+    }
+
+    let mut search = Search {
+        pairs,
+        rules,
+        n,
+        // with one round table everyone has exactly two distinct neighbors
+        tight: rules.tables == 1 && rules.shape == Shape::Round && n >= 3,
+        top1,
+        top2,
+        partners,
+        forbidden,
+        closed: Vec::new(),
+        current: Vec::new(),
+        anchor: 0,
+        seated: vec![false; n],
+        unseated: n,
+        best: None,
+        progress,
+    };
+    search.next(0)?;
+    Ok(search.best)
+}
+
+struct Search<'a> {
+    pairs: &'a UpperTriangular<i32>,
+    rules: &'a Rules,
+    n: usize,
+    tight: bool,
+    /// Each guest's single best pairing
+    top1: Vec<i32>,
+    /// Each guest's two best pairings combined
+    top2: Vec<i32>,
+    /// Who each guest is required to sit next to
+    partners: Vec<Vec<usize>>,
+    /// `n * n` table of pairs that may not be neighbors
+    forbidden: Vec<bool>,
+    closed: Vec<Vec<usize>>,
+    current: Vec<usize>,
+    /// Lowest guest that was unseated when the current table was started
+    anchor: usize,
+    seated: Vec<bool>,
+    unseated: usize,
+    best: Option<Plan>,
+    progress: &'a Progress,
+}
+
+impl<'a> Search<'a> {
+    /// Twice the most happiness any completion of the current plan could reach
+    fn upper_bound(&self, so_far: i32) -> i32 {
+        let unseated = (0..self.n).filter(|g| !self.seated[*g]);
+        let (open, ends): (i32, i32) = match (self.current.first(), self.current.last()) {
+            (Some(first), Some(last)) if self.tight => (
+                unseated.map(|g| self.top2[g]).sum(),
+                self.top1[*first] + self.top1[*last],
+            ),
+            _ => {
+                let open = unseated.map(|g| std::cmp::max(2 * self.top1[g], 0)).sum();
+                let slot = |g: usize| std::cmp::max(self.top1[g], 0);
+                let ends = match (self.current.first(), self.current.last()) {
+                    (Some(first), Some(last)) => {
+                        let round = if self.rules.shape == Shape::Round {
+                            slot(*first)
+                        } else {
+                            0
+                        };
+                        slot(*last) + round
+                    }
+                    _ => 0,
+                };
+                (open, ends)
+            }
+        };
+        2 * so_far + open + ends
+    }
+
+    /// Whether `guest` is next to everyone they must sit with
+    fn satisfied(&self, guest: usize, neighbors: &[usize]) -> bool {
+        self.partners[guest].iter().all(|p| neighbors.contains(p))
+    }
+
+    fn next(&mut self, so_far: i32) -> Result<()> {
+        // pruned branches can go a long time without finishing a plan
+        self.progress.check()?;
+        if let Some(best) = &self.best {
+            if self.upper_bound(so_far) <= 2 * best.happiness {
+                return Ok(());
+            }
+        }
+
+        if self.current.is_empty() {
+            return self.open_table(so_far);
+        }
+
+        let tables_after = self.rules.tables - self.closed.len() - 1;
+        if self.unseated > tables_after {
+            self.extend_table(so_far)?;
+        }
+        if self.unseated >= tables_after && (tables_after > 0 || self.unseated == 0) {
+            self.close_table(so_far)?;
+        }
+        Ok(())
+    }
+
+    fn open_table(&mut self, so_far: i32) -> Result<()> {
+        self.anchor = (0..self.n)
+            .find(|g| !self.seated[*g])
+            .expect("a table is only opened with guests left");
+        match self.rules.shape {
+            Shape::Round => {
+                let anchor = self.anchor;
+                self.seat(anchor);
+                self.next(so_far)?;
+                self.unseat();
+            }
+            Shape::Linear => {
+                for guest in 0..self.n {
+                    if self.seated[guest] {
+                        continue;
+                    }
+                    self.seat(guest);
+                    self.next(so_far)?;
+                    self.unseat();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn extend_table(&mut self, so_far: i32) -> Result<()> {
+        let len = self.current.len();
+        let last = self.current[len - 1];
+        for guest in 0..self.n {
+            if self.seated[guest] || self.forbidden[last * self.n + guest] {
+                continue;
+            }
+            // `last` now has all of their neighbors, unless a round table wraps back to them
+            let done = match (len, self.rules.shape) {
+                (1, Shape::Round) => true,
+                (1, Shape::Linear) => self.satisfied(last, &[guest]),
+                _ => self.satisfied(last, &[self.current[len - 2], guest]),
+            };
+            if !done {
+                continue;
+            }
+            self.seat(guest);
+            self.next(so_far + self.pairs.get(last, guest))?;
+            self.unseat();
+        }
+        Ok(())
+    }
+
+    fn close_table(&mut self, so_far: i32) -> Result<()> {
+        let table = &self.current;
+        let len = table.len();
+        let first = table[0];
+        let last = table[len - 1];
+        let ok = match self.rules.shape {
+            Shape::Round => match len {
+                1 => self.satisfied(first, &[]),
+                2 => self.satisfied(first, &[last]) && self.satisfied(last, &[first]),
+                _ => {
+                    table[1] < last
+                        && !self.forbidden[last * self.n + first]
+                        && self.satisfied(first, &[table[1], last])
+                        && self.satisfied(last, &[table[len - 2], first])
+                }
+            },
+            Shape::Linear => {
+                let last_ok = match len {
+                    1 => self.satisfied(last, &[]),
+                    _ => first < last && self.satisfied(last, &[table[len - 2]]),
+                };
+                last_ok && table.contains(&self.anchor)
+            }
+        };
+        if !ok {
+            return Ok(());
+        }
+        let closing = match (self.rules.shape, len) {
+            (Shape::Round, 2..=usize::MAX) => self.pairs.get(last, first),
+            _ => 0,
+        };
+
+        let table = std::mem::take(&mut self.current);
+        let anchor = self.anchor;
+        self.closed.push(table);
+        if self.closed.len() == self.rules.tables {
+            self.finish()?;
+        } else {
+            self.next(so_far + closing)?;
+        }
+        self.current = self.closed.pop().expect("table was just closed");
+        self.anchor = anchor;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.progress.add(1)?;
+        let total = self
+            .closed
+            .iter()
+            .map(|t| match self.rules.shape {
+                Shape::Round => graph::cycle_weight(self.pairs, t),
+                Shape::Linear => graph::path_weight(self.pairs, t),
+            })
+            .sum::<i32>();
+        match &self.best {
+            Some(b) if b.happiness >= total => {}
+            _ => {
+                self.best = Some(Plan {
+                    tables: self.closed.clone(),
+                    happiness: total,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    fn seat(&mut self, guest: usize) {
+        self.seated[guest] = true;
+        self.unseated -= 1;
+        self.current.push(guest);
+    }
+
+    fn unseat(&mut self) {
+        let guest = self.current.pop().expect("someone to unseat");
+        self.seated[guest] = false;
+        self.unseated += 1;
+    }
+}
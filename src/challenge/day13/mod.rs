@@ -1,103 +1,284 @@
-use std::{any, collections::HashMap};
+use std::fmt;
 
 use anyhow::{Context, Result};
-use itertools::Itertools;
-use matrix::Matrix;
-mod matrix;
-
-fn parse_amount(input: &str) -> Result<i32> {
-    let mut chunks = input.split(' ');
-    let dir = chunks
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("could not get gain/lose"))?;
-    let scalar_str = chunks
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("could not get number text"))?;
-    let v = scalar_str
-        .parse::<i32>()
-        .with_context(|| format!("could not parse number: {:?}", scalar_str))?;
-    let d = match dir {
-        "gain" => 1,
-        "lose" => -1,
-        _ => anyhow::bail!("direction was not gain or lose: {:?}", dir),
-    };
-    Ok(d * v)
-}
-
-fn parse_line(input: &str) -> Result<(&str, &str, i32)> {
-    let input = input.trim_end_matches('.');
-    let mut chunks = input
-        .split(" happiness units by sitting next to ")
-        .flat_map(|s| s.split(" would "));
-
-    let src = chunks
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("could not get src"))?;
-    let amount = chunks
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("could not get gain/lose amount"))?;
-    let dst = chunks
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("could not get gain/lose amount"))?;
-    let h = parse_amount(amount).context("parse number from amount")?;
-    Ok((src, dst, h))
-}
-
-fn parse_input(input: &str, add_extra: bool) -> Result<Matrix> {
-    let mut loc_map = HashMap::new();
-    let mut loc_to_id = |loc: &str| {
-        let seen = loc_map.len();
-        *loc_map.entry(loc.to_string()).or_insert(seen)
-    };
+
+use super::{
+    graph::{Interner, Matrix},
+    options::Format,
+    progress::Progress,
+    Options,
+};
+
+mod branch_bound;
+mod parser;
+use branch_bound::{Plan, Rules, Shape};
+
+/// Name used for the extra seat added in part 2
+const HOST_NAME: &str = "You";
+
+/// Everyone at the table and how they feel about each other
+#[derive(Debug)]
+struct Guests {
+    names: Interner,
+    /// Id of the indifferent extra guest, if there is one
+    host: Option<usize>,
+    /// `happiness.get(a, b)` is how `a` feels about sitting next to `b`
+    happiness: Matrix<i32>,
+}
+
+impl Guests {
+    fn id(&self, name: &str) -> Result<usize> {
+        match self.host {
+            Some(host) if name == HOST_NAME => Ok(host),
+            _ => self
+                .names
+                .id(name)
+                .ok_or_else(|| anyhow::anyhow!("unknown guest: {:?}", name)),
+        }
+    }
+
+    fn name(&self, id: usize) -> &str {
+        if self.host == Some(id) {
+            HOST_NAME
+        } else {
+            self.names.name(id)
+        }
+    }
+}
+
+fn parse_input(input: &str, add_extra: bool) -> Result<Guests> {
+    let mut names = Interner::default();
     let mut buf = Vec::new();
 
-    for line in input.lines() {
-        let (src, dst, h) =
-            parse_line(line).with_context(|| format!("unable to parse line: {:?}", line))?;
-        log::trace!("src: {} dst: {} dist: {}", src, dst, h);
-        buf.push((loc_to_id(src), loc_to_id(dst), h))
+    for (idx, line) in input.lines().enumerate() {
+        let p = parser::parse_line(line)
+            .with_context(|| format!("line {}: unable to parse {:?}", idx + 1, line))?;
+        log::trace!("{:?}", p);
+        buf.push((names.intern(p.guest), names.intern(p.neighbor), p.happiness))
     }
+    let host = if add_extra { Some(names.len()) } else { None };
     let host_room = if add_extra { 1 } else { 0 };
-    let mut m = Matrix::new(loc_map.len() + host_room);
+    let mut m = Matrix::new(names.len() + host_room);
     for (x, y, v) in buf {
         m.set(x, y, v);
     }
-    Ok(m)
+    Ok(Guests {
+        names,
+        host,
+        happiness: m,
+    })
 }
 
-fn permute_distances(h_matrix: &Matrix) -> impl Iterator<Item = i32> + '_ {
-    (0..h_matrix.size())
-        .permutations(h_matrix.size())
-        .map(move |p| {
-            p.iter()
-                .cycle()
-                .take(h_matrix.size() + 1)
-                .tuple_windows()
-                .map(move |(s, d)| h_matrix.get(*s, *d) + h_matrix.get(*d, *s))
-                .sum::<i32>()
-        })
+/// Someone sitting beside a guest, and how the guest feels about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbor {
+    pub guest: String,
+    pub happiness: i32,
+}
+
+/// One guest and how they feel about the people on either side
+///
+/// The ends of a straight table only have one neighbor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seat {
+    pub guest: String,
+    pub is_host: bool,
+    pub left: Option<Neighbor>,
+    pub right: Option<Neighbor>,
+}
+
+impl Seat {
+    pub fn happiness(&self) -> i32 {
+        self.neighbors().map(|n| n.happiness).sum()
+    }
+
+    fn neighbors(&self) -> impl Iterator<Item = &Neighbor> {
+        self.left.iter().chain(self.right.iter())
+    }
 }
 
-fn best_seating(input: &str, add_host: bool) -> Result<i32> {
-    let m = parse_input(input, add_host)?;
-    permute_distances(&m)
-        .max()
-        .ok_or_else(|| anyhow::anyhow!("there were no seating arrangements"))
+/// Guests in seat order at each table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arrangement {
+    pub tables: Vec<Vec<Seat>>,
+    pub happiness: i32,
+}
+
+impl Arrangement {
+    fn from_plan(guests: &Guests, shape: Shape, plan: Plan) -> Arrangement {
+        let neighbor = |guest: usize, other: usize| Neighbor {
+            guest: guests.name(other).to_string(),
+            happiness: guests.happiness.get(guest, other),
+        };
+        let tables = plan
+            .tables
+            .iter()
+            .map(|table| {
+                let n = table.len();
+                (0..n)
+                    .map(|i| {
+                        let guest = table[i];
+                        let (left, right) = match shape {
+                            _ if n == 1 => (None, None),
+                            Shape::Round => (Some((i + n - 1) % n), Some((i + 1) % n)),
+                            Shape::Linear => (i.checked_sub(1), Some(i + 1).filter(|r| *r < n)),
+                        };
+                        Seat {
+                            guest: guests.name(guest).to_string(),
+                            is_host: guests.host == Some(guest),
+                            left: left.map(|l| neighbor(guest, table[l])),
+                            right: right.map(|r| neighbor(guest, table[r])),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Arrangement {
+            tables,
+            happiness: plan.happiness,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let neighbor = |n: &Option<Neighbor>| {
+            n.as_ref()
+                .map(|n| serde_json::json!({ "guest": n.guest, "happiness": n.happiness }))
+        };
+        let tables = self
+            .tables
+            .iter()
+            .map(|table| {
+                let seats = table
+                    .iter()
+                    .map(|s| {
+                        serde_json::json!({
+                            "guest": s.guest,
+                            "host": s.is_host,
+                            "left": neighbor(&s.left),
+                            "right": neighbor(&s.right),
+                            "happiness": s.happiness(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let happiness = table.iter().map(|s| s.happiness()).sum::<i32>();
+                serde_json::json!({ "happiness": happiness, "seats": seats })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({ "happiness": self.happiness, "tables": tables })
+    }
+}
+
+impl fmt::Display for Arrangement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "total happiness: {}", self.happiness)?;
+        let multiple = self.tables.len() > 1;
+        for (idx, table) in self.tables.iter().enumerate() {
+            if multiple {
+                let happiness = table.iter().map(|s| s.happiness()).sum::<i32>();
+                writeln!(f, "table {}: {}", idx + 1, happiness)?;
+            }
+            for s in table {
+                let indent = if multiple { "  " } else { "" };
+                let label = if s.is_host { " (host)" } else { "" };
+                let neighbors = s
+                    .neighbors()
+                    .map(|n| format!("{:+} next to {}", n.happiness, n.guest))
+                    .collect::<Vec<_>>();
+                let neighbors = if neighbors.is_empty() {
+                    "alone".to_string()
+                } else {
+                    neighbors.join(", ")
+                };
+                writeln!(
+                    f,
+                    "{}{}{}: {:+} ({})",
+                    indent,
+                    s.guest,
+                    label,
+                    s.happiness(),
+                    neighbors
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn plan_seating(guests: &Guests, rules: &Rules) -> Result<Arrangement> {
+    let pairs = guests.happiness.symmetric_sum();
+    let progress = Progress::new("day13 seatings", None);
+    let best = branch_bound::best_plan(&pairs, rules, &progress)?;
+    progress.finish();
+    let plan = best.ok_or_else(|| anyhow::anyhow!("there were no seating arrangements"))?;
+    Ok(Arrangement::from_plan(guests, rules.shape, plan))
+}
+
+fn best_seating(input: &str, add_host: bool) -> Result<Arrangement> {
+    let guests = parse_input(input, add_host)?;
+    plan_seating(&guests, &Rules::default())
 }
 
 pub fn part1(input: &str) -> Result<String> {
     let x = best_seating(input, false)?;
-    Ok(format!("{:?}", x))
+    Ok(format!("{:?}", x.happiness))
 }
 
 pub fn part2(input: &str) -> Result<String> {
     let x = best_seating(input, true)?;
-    Ok(format!("{:?}", x))
+    Ok(format!("{:?}", x.happiness))
+}
+
+/// `A:B,C:D` into pairs of guest ids
+fn parse_pairs(guests: &Guests, list: Option<&str>) -> Result<Vec<(usize, usize)>> {
+    list.into_iter()
+        .flat_map(|l| l.split(','))
+        .map(|pair| {
+            let (a, b) = pair
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("pair was not in A:B form: {:?}", pair))?;
+            let (a, b) = (guests.id(a.trim())?, guests.id(b.trim())?);
+            if a == b {
+                anyhow::bail!("a guest cannot be paired with themselves: {:?}", pair);
+            }
+            Ok((a, b))
+        })
+        .collect()
+}
+
+fn parse_rules(guests: &Guests, opts: &Options) -> Result<Rules> {
+    let default = Rules::default();
+    Ok(Rules {
+        tables: opts.get("tables")?.unwrap_or(default.tables),
+        shape: opts.get("shape")?.unwrap_or(default.shape),
+        together: parse_pairs(guests, opts.get_str("together"))?,
+        apart: parse_pairs(guests, opts.get_str("apart"))?,
+    })
+}
+
+/// Show the best tables and everyone's happiness with their neighbors
+///
+/// Options: `host=true` to add yourself as in part 2, `tables=K`,
+/// `shape=round|linear`, `together=A:B,...` and `apart=A:B,...` by name,
+/// and `format=text|json`
+pub fn seating(input: &str, opts: &Options) -> Result<String> {
+    let add_host = opts.get("host")?.unwrap_or(false);
+    let guests = parse_input(input, add_host)?;
+    let rules = parse_rules(&guests, opts)?;
+    log::debug!("{:?}", rules);
+    let arrangement = plan_seating(&guests, &rules)?;
+    match opts.format()? {
+        Format::Text => Ok(arrangement.to_string().trim_end().to_string()),
+        Format::Json => Ok(serde_json::to_string_pretty(&arrangement.to_json())?),
+        Format::Csv => anyhow::bail!("seating can only be shown as text or json"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use itertools::Itertools;
+
     use super::*;
+    use crate::challenge::graph::{self, UpperTriangular};
     const INPUT: &str = include_str!("../../../input/day13");
     const EX: &str = include_str!("../../../input/day13_ex");
 
@@ -114,4 +295,215 @@ mod tests {
     fn check_example() {
         assert_eq!(part1(EX).unwrap().as_str(), "330")
     }
+
+    /// Best happiness of every way to cut every seating order into tables
+    fn brute_force(pairs: &UpperTriangular<i32>, rules: &Rules) -> Option<i32> {
+        let n = pairs.size();
+        let table_weight = |t: &[usize]| match rules.shape {
+            Shape::Round => graph::cycle_weight(pairs, t),
+            Shape::Linear => graph::path_weight(pairs, t),
+        };
+        let adjacent = |tables: &[&[usize]], a: usize, b: usize| {
+            tables.iter().any(|t| {
+                let wraps = rules.shape == Shape::Round && t.len() > 1;
+                let closing = (t[t.len() - 1], t[0]);
+                t.windows(2)
+                    .map(|w| (w[0], w[1]))
+                    .chain(Some(closing).filter(|_| wraps))
+                    .any(|(x, y)| (x, y) == (a, b) || (x, y) == (b, a))
+            })
+        };
+        let mut best = None;
+        for p in (0..n).permutations(n) {
+            for cuts in (1..n).combinations(rules.tables - 1) {
+                let bounds = std::iter::once(0)
+                    .chain(cuts)
+                    .chain(std::iter::once(n))
+                    .collect::<Vec<_>>();
+                let tables = bounds
+                    .windows(2)
+                    .map(|w| &p[w[0]..w[1]])
+                    .collect::<Vec<_>>();
+                if !rules
+                    .together
+                    .iter()
+                    .all(|(a, b)| adjacent(&tables, *a, *b))
+                    || rules.apart.iter().any(|(a, b)| adjacent(&tables, *a, *b))
+                {
+                    continue;
+                }
+                let total = tables.iter().map(|t| table_weight(t)).sum::<i32>();
+                best = std::cmp::max(best, Some(total));
+            }
+        }
+        best
+    }
+
+    /// Happiness from a square of rows, ignoring the diagonal
+    fn matrix(rows: &[&[i32]]) -> Matrix<i32> {
+        let mut m = Matrix::new(rows.len());
+        for (x, row) in rows.iter().enumerate() {
+            for (y, h) in row.iter().enumerate() {
+                if x != y {
+                    m.set(x, y, *h);
+                }
+            }
+        }
+        m
+    }
+
+    fn assert_matches_brute_force(m: &Matrix<i32>, rules: &Rules) {
+        let pairs = m.symmetric_sum();
+        let p = Progress::new("test", None);
+        let best = branch_bound::best_plan(&pairs, rules, &p).unwrap();
+        assert_eq!(
+            best.as_ref().map(|t| t.happiness),
+            brute_force(&pairs, rules),
+            "{:?}",
+            rules
+        );
+        if let Some(plan) = best {
+            assert_eq!(plan.tables.len(), rules.tables);
+            assert_eq!(
+                plan.tables.iter().map(|t| t.len()).sum::<usize>(),
+                pairs.size()
+            );
+        }
+    }
+
+    #[test]
+    fn branch_bound_matches_permutations() {
+        let happiness = parse_input(INPUT, false).unwrap().happiness;
+        for shape in [Shape::Round, Shape::Linear] {
+            for tables in 1..4 {
+                let rules = Rules {
+                    tables,
+                    shape,
+                    ..Rules::default()
+                };
+                assert_matches_brute_force(&happiness, &rules);
+                let rules = Rules {
+                    together: vec![(0, 3), (3, 5)],
+                    apart: vec![(1, 2), (0, 4)],
+                    ..rules
+                };
+                assert_matches_brute_force(&happiness, &rules);
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_parties() {
+        let rules = Rules::default();
+        assert_matches_brute_force(&matrix(&[&[0]]), &rules);
+        assert_matches_brute_force(&matrix(&[&[0, 5], &[-7, 0]]), &rules);
+        let three = matrix(&[&[0, 5, -3], &[2, 0, 8], &[-1, 4, 0]]);
+        assert_matches_brute_force(&three, &rules);
+        let rules = Rules {
+            tables: 3,
+            shape: Shape::Linear,
+            ..rules
+        };
+        assert_matches_brute_force(&three, &rules);
+    }
+
+    #[test]
+    fn impossible_rules() {
+        let pairs = parse_input(EX, false).unwrap().happiness.symmetric_sum();
+        let p = Progress::new("test", None);
+        // Alice only has two neighbors
+        let rules = Rules {
+            together: vec![(0, 1), (0, 2), (0, 3)],
+            ..Rules::default()
+        };
+        assert_eq!(branch_bound::best_plan(&pairs, &rules, &p).unwrap(), None);
+        let rules = Rules {
+            tables: 5,
+            ..Rules::default()
+        };
+        assert_eq!(branch_bound::best_plan(&pairs, &rules, &p).unwrap(), None);
+    }
+
+    #[test]
+    fn prunes_redundant_tables() {
+        let pairs = parse_input(INPUT, true).unwrap().happiness.symmetric_sum();
+        let p = Progress::new("test", None);
+        branch_bound::best_plan(&pairs, &Rules::default(), &p).unwrap();
+        // 9 guests have 8!/2 distinct tables
+        assert!(p.done() <= 20160);
+    }
+
+    #[test]
+    fn example_arrangement() {
+        let a = best_seating(EX, false).unwrap();
+        assert_eq!(a.happiness, 330);
+        assert_eq!(a.tables.len(), 1);
+        let seats = &a.tables[0];
+        assert_eq!(seats.iter().map(|s| s.happiness()).sum::<i32>(), 330);
+        let alice = seats.iter().find(|s| s.guest == "Alice").unwrap();
+        let mut neighbors = alice
+            .neighbors()
+            .map(|n| (n.guest.as_str(), n.happiness))
+            .collect::<Vec<_>>();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![("Bob", 54), ("David", -2)]);
+        assert!(seats.iter().all(|s| !s.is_host));
+    }
+
+    #[test]
+    fn host_is_labelled() {
+        let a = best_seating(INPUT, true).unwrap();
+        assert_eq!(a.happiness, 725);
+        let hosts = a.tables[0].iter().filter(|s| s.is_host).collect::<Vec<_>>();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].guest, HOST_NAME);
+        assert_eq!(hosts[0].happiness(), 0);
+
+        let json = a.to_json();
+        assert_eq!(json["happiness"], 725);
+        assert_eq!(json["tables"][0]["seats"].as_array().unwrap().len(), 9);
+    }
+
+    #[test]
+    fn linear_ends_have_one_neighbor() {
+        let guests = parse_input(EX, false).unwrap();
+        let rules = Rules {
+            shape: Shape::Linear,
+            together: vec![(guests.id("Alice").unwrap(), guests.id("Carol").unwrap())],
+            ..Rules::default()
+        };
+        let a = plan_seating(&guests, &rules).unwrap();
+        let seats = &a.tables[0];
+        assert!(seats[0].left.is_none());
+        assert!(seats[3].right.is_none());
+        assert_eq!(
+            seats.iter().map(|s| s.happiness()).sum::<i32>(),
+            a.happiness
+        );
+        let alice = seats.iter().find(|s| s.guest == "Alice").unwrap();
+        assert!(alice.neighbors().any(|n| n.guest == "Carol"));
+    }
+
+    #[test]
+    fn error_names_line_and_field() {
+        let input = "Alice would gain 54 happiness units by sitting next to Bob.\n\
+                     Bob would gain 83 happiness units by sitting next to Alice!";
+        let err = parse_input(input, false).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "line 2: unable to parse \"Bob would gain 83 happiness units by sitting next to Alice!\": \
+             expected \".\" after neighbor at column 59, found \"!\""
+        );
+    }
+
+    #[test]
+    fn rules_from_options() {
+        let guests = parse_input(EX, true).unwrap();
+        let pairs = parse_pairs(&guests, Some("Alice:Bob, You:David")).unwrap();
+        assert_eq!(pairs, vec![(0, 1), (4, 3)]);
+        assert!(parse_pairs(&guests, Some("Alice:Zed")).is_err());
+        assert!(parse_pairs(&guests, Some("Alice")).is_err());
+        assert!(parse_pairs(&guests, Some("Alice:Alice")).is_err());
+        assert_eq!(parse_pairs(&guests, None).unwrap(), vec![]);
+    }
 }
@@ -0,0 +1,173 @@
+//! Parser for the happiness sentence
+//!
+//! ```text
+//! <guest> would <gain|lose> <amount> happiness units by sitting next to <neighbor>.
+//! ```
+//!
+//! Each piece is read in turn, so an error names the field that was wrong and
+//! the column where it started.
+
+use anyhow::{anyhow as ah, Result};
+
+use crate::challenge::sentence::Cursor;
+
+/// One line of input: how `guest` feels about sitting next to `neighbor`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preference<'a> {
+    pub guest: &'a str,
+    pub neighbor: &'a str,
+    pub happiness: i32,
+}
+
+pub fn parse_line(line: &str) -> Result<Preference<'_>> {
+    let mut c = Cursor::new(line);
+
+    let guest = c.word("guest")?;
+    c.literal(" would ", "guest")?;
+
+    let direction_col = c.col();
+    let sign = match c.word("gain/lose")? {
+        "gain" => 1,
+        "lose" => -1,
+        other => {
+            return Err(ah!(
+                "expected `gain` or `lose` at column {}, found {:?}",
+                direction_col,
+                other
+            ))
+        }
+    };
+    c.literal(" ", "gain/lose")?;
+
+    let amount = c.number::<i32>("amount")?;
+    c.literal(" happiness units by sitting next to ", "amount")?;
+
+    let neighbor = c.word("neighbor")?;
+    c.literal(".", "neighbor")?;
+    c.end()?;
+
+    if guest == neighbor {
+        return Err(ah!("{} cannot sit next to themselves", guest));
+    }
+
+    Ok(Preference {
+        guest,
+        neighbor,
+        happiness: sign * amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn err(line: &str) -> String {
+        parse_line(line).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn gain_and_lose() {
+        assert_eq!(
+            parse_line("Alice would gain 54 happiness units by sitting next to Bob.").unwrap(),
+            Preference {
+                guest: "Alice",
+                neighbor: "Bob",
+                happiness: 54
+            }
+        );
+        assert_eq!(
+            parse_line("Bob would lose 7 happiness units by sitting next to Carol.")
+                .unwrap()
+                .happiness,
+            -7
+        );
+    }
+
+    #[test]
+    fn missing_guest() {
+        assert_eq!(
+            err(" would gain 54 happiness units by sitting next to Bob."),
+            "missing guest at column 1, found \" would gain 54 happiness units by sitting next to Bob.\""
+        );
+    }
+
+    #[test]
+    fn bad_would() {
+        assert_eq!(
+            err("Alice will gain 54 happiness units by sitting next to Bob."),
+            "expected \" would \" after guest at column 6, found \" will gain 54 happiness units by sitting next to Bob.\""
+        );
+    }
+
+    #[test]
+    fn bad_direction() {
+        assert_eq!(
+            err("Alice would win 54 happiness units by sitting next to Bob."),
+            "expected `gain` or `lose` at column 13, found \"win\""
+        );
+    }
+
+    #[test]
+    fn missing_amount() {
+        assert_eq!(
+            err("Alice would gain happiness units by sitting next to Bob."),
+            "amount \"happiness\" at column 18 is not a number: invalid digit found in string"
+        );
+        assert_eq!(
+            err("Alice would gain -54 happiness units by sitting next to Bob."),
+            "missing amount at column 18, found \"-54 happiness units by sitting next to Bob.\""
+        );
+    }
+
+    #[test]
+    fn bad_amount() {
+        assert_eq!(
+            err("Alice would gain lots happiness units by sitting next to Bob."),
+            "amount \"lots\" at column 18 is not a number: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn bad_middle() {
+        assert_eq!(
+            err("Alice would gain 54 happiness points by sitting next to Bob."),
+            "expected \" happiness units by sitting next to \" after amount at column 20, found \" happiness points by sitting next to Bob.\""
+        );
+    }
+
+    #[test]
+    fn missing_neighbor() {
+        assert_eq!(
+            err("Alice would gain 54 happiness units by sitting next to ."),
+            "missing neighbor at column 56, found \".\""
+        );
+    }
+
+    #[test]
+    fn missing_period() {
+        assert_eq!(
+            err("Alice would gain 54 happiness units by sitting next to Bob"),
+            "expected \".\" after neighbor at column 59, found \"\""
+        );
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        assert_eq!(
+            err("Alice would gain 54 happiness units by sitting next to Bob. Really."),
+            "unexpected trailing text at column 60: \" Really.\""
+        );
+        assert_eq!(
+            err("Alice would gain 54 happiness units by sitting next to Bob and Carol."),
+            "expected \".\" after neighbor at column 59, found \" and Carol.\""
+        );
+    }
+
+    #[test]
+    fn self_pairing() {
+        assert_eq!(
+            err("Alice would gain 54 happiness units by sitting next to Alice."),
+            "Alice cannot sit next to themselves"
+        );
+    }
+}
@@ -1,108 +1,311 @@
+use std::fmt::Write;
+
 use anyhow::{anyhow, Context, Result};
 
+use super::{options::Format, Options};
+
+mod parser;
+mod scoring;
+use scoring::Scoring;
+
 const RACE_DURATION: u32 = 2503;
 
-#[derive(Debug, Clone, Copy)]
+/// The longest race written out second by second as CSV
+const CSV_SECONDS_MAX: u32 = 100_000;
+
+#[derive(Debug, Clone)]
 struct Reindeer {
+    name: String,
     speed: u32,
     fly: u32,
     rest: u32,
 }
 
 impl Reindeer {
-    fn distance_during_time(&self, total_seconds: u32) -> u32 {
-        let cycle = self.fly + self.rest;
-        let interval = total_seconds % cycle;
-        if interval >= self.fly {
+    /// Seconds of flying and resting, which can overflow a `u32`
+    fn cycle(&self) -> u64 {
+        self.fly as u64 + self.rest as u64
+    }
+
+    /// How far the reindeer moves during second `total_seconds` (counting from 0)
+    fn distance_during_time(&self, total_seconds: u32) -> u64 {
+        let interval = total_seconds as u64 % self.cycle();
+        if interval >= self.fly as u64 {
             0
         } else {
-            self.speed
+            self.speed as u64
         }
     }
 
-    fn distance(&self, seconds: u32) -> u32 {
-        let cycle = self.fly + self.rest;
+    fn distance(&self, seconds: u32) -> u64 {
+        let cycle = self.cycle();
+        let seconds = seconds as u64;
+        let fly = self.fly as u64;
         let full_cycles = seconds / cycle;
         let last_cycle = seconds % cycle;
-        let seconds_flying = if last_cycle < self.fly {
-            full_cycles * self.fly + last_cycle
+        let seconds_flying = if last_cycle < fly {
+            full_cycles * fly + last_cycle
         } else {
-            (full_cycles + 1) * self.fly
+            (full_cycles + 1) * fly
         };
-        seconds_flying * self.speed
+        seconds_flying * self.speed as u64
+    }
+
+    /// The first time after `now` when the reindeer starts or stops flying
+    fn next_switch(&self, now: u32) -> u64 {
+        let cycle = self.cycle();
+        let phase = now as u64 % cycle;
+        let cycle_start = now as u64 - phase;
+        if phase < self.fly as u64 {
+            cycle_start + self.fly as u64
+        } else {
+            cycle_start + cycle
+        }
     }
 }
 
-fn parse_single_reindeer(input: &str) -> Result<Reindeer> {
-    let mut words = input.split(' ');
-    let mut parse_number = |n: usize| -> Result<u32> {
-        let s = words
-            .nth(n)
-            .ok_or_else(|| anyhow::anyhow!("not enough elements in input"))?;
-        let x = s
-            .parse::<u32>()
-            .with_context(|| format!("convert `{}` to number", s))?;
-        Ok(x)
-    };
+fn parse_input(input: &str) -> Result<Vec<Reindeer>> {
+    let mut res: Vec<Reindeer> = Vec::new();
+    for (idx, line) in input.lines().enumerate() {
+        let r = parser::parse_line(line)
+            .with_context(|| format!("line {}: unable to parse {:?}", idx + 1, line))?;
+        if let Some(prev) = res.iter().position(|p| p.name == r.name) {
+            anyhow::bail!(
+                "line {}: {} is already racing on line {}",
+                idx + 1,
+                r.name,
+                prev + 1
+            );
+        }
+        res.push(r);
+    }
+    Ok(res)
+}
 
-    let speed = parse_number(3).context("parse speed")?;
-    let fly = parse_number(2).context("parse fly")?;
-    let rest = parse_number(6).context("parse rest")?;
+/// Where everyone stands once a second of the race has finished
+#[derive(Debug, Clone, PartialEq)]
+struct Standing {
+    /// Seconds since the start, from 1
+    second: u32,
+    distances: Vec<u64>,
+    points: Vec<u32>,
+    /// Everyone tied for the furthest distance, who each got a point this second
+    leaders: Vec<usize>,
+}
 
-    Ok(Reindeer { speed, fly, rest })
+impl Standing {
+    /// Standings after `second` seconds, without running the race up to them
+    fn after(racers: &[Reindeer], second: u32) -> Standing {
+        let distances = racers
+            .iter()
+            .map(|r| r.distance(second))
+            .collect::<Vec<_>>();
+        let max_distance = distances.iter().max().cloned().unwrap_or(0);
+        Standing {
+            second,
+            leaders: (0..racers.len())
+                .filter(|i| distances[*i] == max_distance)
+                .collect(),
+            points: scoring::lead_points(racers, second),
+            distances,
+        }
+    }
+
+    /// Racers ranked by points, then by distance
+    fn leaderboard(&self, names: &[String]) -> String {
+        let mut order = (0..names.len()).collect::<Vec<_>>();
+        order.sort_by_key(|i| {
+            (
+                std::cmp::Reverse(self.points[*i]),
+                std::cmp::Reverse(self.distances[*i]),
+            )
+        });
+        let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+        let mut out = format!("after {}s:\n", self.second);
+        for (rank, i) in order.iter().enumerate() {
+            let lead = if self.leaders.contains(i) { " *" } else { "" };
+            writeln!(
+                out,
+                "{:>3}. {:<width$} {:>6} km {:>5} pts{}",
+                rank + 1,
+                names[*i],
+                self.distances[*i],
+                self.points[*i],
+                lead,
+                width = width
+            )
+            .expect("writing to a string");
+        }
+        out
+    }
 }
 
-fn parse_input(input: &str) -> Result<Vec<Reindeer>> {
-    let mut res = Vec::new();
-    for line in input.lines() {
-        res.push(parse_single_reindeer(line)?);
+/// Every second of a race, in order
+#[derive(Debug)]
+struct Race {
+    names: Vec<String>,
+    history: Vec<Standing>,
+}
+
+impl Race {
+    fn run(racers: &[Reindeer], time: u32) -> Race {
+        let mut points = vec![0u32; racers.len()];
+        let mut distances = vec![0u64; racers.len()];
+        let mut history = Vec::with_capacity(time as usize);
+
+        for t in 0..time {
+            for (r, dist) in racers.iter().zip(&mut distances) {
+                *dist += r.distance_during_time(t);
+            }
+            let max_distance = distances.iter().max().cloned().unwrap_or(0);
+            let leaders = (0..racers.len())
+                .filter(|i| distances[*i] == max_distance)
+                .collect::<Vec<_>>();
+            for i in &leaders {
+                points[*i] += 1;
+            }
+            if t < 100 {
+                log::trace!("t={}, Distance: {:?} (max={})", t, distances, max_distance);
+                log::trace!("t={}, Points:   {:?}", t, points);
+            }
+            history.push(Standing {
+                second: t + 1,
+                distances: distances.clone(),
+                points: points.clone(),
+                leaders,
+            });
+        }
+
+        Race {
+            names: racers.iter().map(|r| r.name.clone()).collect(),
+            history,
+        }
+    }
+
+    /// Standings after `second` seconds, or `None` before the start or after the finish
+    #[cfg(test)]
+    fn at(&self, second: u32) -> Option<&Standing> {
+        match second {
+            0 => None,
+            s => self.history.get(s as usize - 1),
+        }
+    }
+
+    /// One row per racer per second, for plotting
+    fn to_csv(&self) -> String {
+        let mut out = String::from("second,name,distance,points,leading\n");
+        for standing in &self.history {
+            for (i, name) in self.names.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    standing.second,
+                    name,
+                    standing.distances[i],
+                    standing.points[i],
+                    standing.leaders.contains(&i)
+                )
+                .expect("writing to a string");
+            }
+        }
+        out
     }
-    Ok(res)
 }
 
-fn winning_distance_after_time(input: &str, time: u32) -> Result<Option<u32>> {
-    let racers = parse_input(input)?;
-    log::trace!("{:?}", racers);
-    Ok(racers.iter().map(|r| r.distance(time)).max())
+/// Race length from `duration=S`, defaulting to the puzzle's
+fn duration(opts: &Options) -> Result<u32> {
+    Ok(opts.get("duration")?.unwrap_or(RACE_DURATION))
 }
 
-fn winning_points_after_time(input: &str, time: u32) -> Result<Option<u32>> {
+fn winning_score(input: &str, time: u32, scoring: Scoring) -> Result<String> {
     let racers = parse_input(input)?;
     log::trace!("{:?}", racers);
+    let best = scoring::scores(&racers, time, scoring)?
+        .into_iter()
+        .max()
+        .ok_or_else(|| anyhow::anyhow!("there were no racers"))?;
+    Ok(best.to_string())
+}
 
-    let mut points = vec![0u32; racers.len()];
-    let mut distances = vec![0u32; racers.len()];
-    let mut max_distance = 0;
+/// Comma separated seconds, e.g. `at=1,140,1000`
+fn parse_timestamps(list: &str) -> Result<Vec<u32>> {
+    list.split(',')
+        .map(|s| {
+            s.trim()
+                .parse::<u32>()
+                .with_context(|| format!("invalid timestamp {:?}", s))
+        })
+        .collect()
+}
 
-    for t in 0..time {
-        if t < 100 {
-            log::trace!("t={}, Distance: {:?} (max={})", t, distances, max_distance);
-            log::trace!("t={}, Points:   {:?}", t, points);
-        }
-        for (r, dist) in racers.iter().zip(&mut distances) {
-            *dist += r.distance_during_time(t);
-            max_distance = std::cmp::max(max_distance, *dist);
+/// Print the leaderboard partway through the race, or the whole race as CSV
+///
+/// Options: `duration=S`, `at=S,S,...` seconds to show (default the end of
+/// the race) and `format=text|csv`. Text standings are worked out directly for
+/// any duration, but CSV lists every second so stops at [`CSV_SECONDS_MAX`].
+pub fn race(input: &str, opts: &Options) -> Result<String> {
+    let racers = parse_input(input)?;
+    let end = duration(opts)?;
+    let at = match opts.get_str("at") {
+        Some(list) => parse_timestamps(list)?,
+        None => vec![end],
+    };
+    if let Some(s) = at.iter().find(|s| **s == 0 || **s > end) {
+        anyhow::bail!(
+            "there are no standings at {}s, the race runs 1s to {}s",
+            s,
+            end
+        );
+    }
+
+    match opts.format()? {
+        Format::Text => {
+            let names = racers.iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+            let boards = at
+                .iter()
+                .map(|s| Standing::after(&racers, *s).leaderboard(&names))
+                .collect::<Vec<_>>();
+            Ok(boards.join("\n").trim_end().to_string())
         }
-        for (p, dist) in points.iter_mut().zip(&distances) {
-            if *dist >= max_distance {
-                *p += 1
+        Format::Csv => {
+            if end > CSV_SECONDS_MAX {
+                anyhow::bail!(
+                    "a {}s race is too long for csv, which stops at {}s",
+                    end,
+                    CSV_SECONDS_MAX
+                );
             }
+            Ok(Race::run(&racers, end).to_csv().trim_end().to_string())
         }
+        Format::Json => anyhow::bail!("the race can only be shown as text or csv"),
     }
+}
 
-    Ok(points.iter().max().cloned())
+/// Everyone's final score, best first
+///
+/// Options: `duration=S` and `scoring=distance|lead|split|top:N` (default `lead`)
+pub fn standings(input: &str, opts: &Options) -> Result<String> {
+    let racers = parse_input(input)?;
+    let scoring = opts.get("scoring")?.unwrap_or(Scoring::Lead);
+    let scores = scoring::scores(&racers, duration(opts)?, scoring)?;
+    let mut order = (0..racers.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| scores[*b].cmp(&scores[*a]));
+    let width = racers.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    let lines = order
+        .iter()
+        .map(|i| format!("{:<width$} {}", racers[*i].name, scores[*i], width = width))
+        .collect::<Vec<_>>();
+    Ok(lines.join("\n"))
 }
 
-pub fn part1(input: &str) -> Result<String> {
-    let distance = winning_distance_after_time(input, RACE_DURATION)?
-        .ok_or_else(|| anyhow::anyhow!("there were no racers"))?;
-    Ok(format!("{:?}", distance))
+/// Options: `duration=S`
+pub fn part1(input: &str, opts: &Options) -> Result<String> {
+    winning_score(input, duration(opts)?, Scoring::Distance)
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let points = winning_points_after_time(input, RACE_DURATION)?
-        .ok_or_else(|| anyhow::anyhow!("there were no racers"))?;
-    Ok(format!("{:?}", points))
+/// Options: `duration=S`
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    winning_score(input, duration(opts)?, Scoring::Lead)
 }
 
 #[cfg(test)]
@@ -111,21 +314,246 @@ mod tests {
     const INPUT: &str = include_str!("../../../input/day14");
     const EX: &str = include_str!("../../../input/day14_ex");
 
+    fn seconds(s: &str) -> Options {
+        Options::from_pairs(&[("duration", s)])
+    }
+
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "2640")
+        assert_eq!(part1(INPUT, &Options::default()).unwrap().as_str(), "2640")
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "1102")
+        assert_eq!(part2(INPUT, &Options::default()).unwrap().as_str(), "1102")
     }
 
     #[test]
     fn check_p1_example() {
-        assert_eq!(winning_distance_after_time(EX, 1000).unwrap(), Some(1120))
+        assert_eq!(part1(EX, &seconds("1000")).unwrap().as_str(), "1120")
     }
     #[test]
     fn check_p2_example() {
-        assert_eq!(winning_points_after_time(EX, 1000).unwrap(), Some(689))
+        assert_eq!(part2(EX, &seconds("1000")).unwrap().as_str(), "689")
+    }
+
+    #[test]
+    fn example_timeline() {
+        let racers = parse_input(EX).unwrap();
+        assert_eq!(racers[0].name, "Comet");
+        let race = Race::run(&racers, 1000);
+        assert_eq!(race.history.len(), 1000);
+
+        // Dancer is faster off the line
+        let first = race.at(1).unwrap();
+        assert_eq!(first.distances, vec![14, 16]);
+        assert_eq!(first.points, vec![0, 1]);
+        assert_eq!(first.leaders, vec![1]);
+
+        let end = race.at(1000).unwrap();
+        assert_eq!(end.distances, vec![1120, 1056]);
+        assert_eq!(end.points, vec![312, 689]);
+        assert_eq!(end.leaders, vec![0]);
+
+        for s in 1..=1000 {
+            let standing = race.at(s).unwrap();
+            for (i, r) in racers.iter().enumerate() {
+                assert_eq!(standing.distances[i], r.distance(s));
+            }
+        }
+        assert!(race.at(0).is_none());
+        assert!(race.at(1001).is_none());
+
+        for s in &[1, 10, 11, 140, 1000] {
+            assert_eq!(&Standing::after(&racers, *s), race.at(*s).unwrap());
+        }
+    }
+
+    #[test]
+    fn long_race_standings() {
+        let opts = |pairs: &[(&str, &str)]| Options::from_pairs(pairs);
+        let long = [("duration", "1000000000"), ("at", "10000000")];
+        assert_eq!(
+            race(EX, &opts(&long)).unwrap(),
+            "after 10000000s:\n  \
+             1. Comet  10219020 km 9992527 pts *\n  \
+             2. Dancer 10173504 km  7925 pts"
+        );
+        let csv = [("duration", "1000000000"), ("format", "csv")];
+        assert_eq!(
+            race(EX, &opts(&csv)).unwrap_err().to_string(),
+            "a 1000000000s race is too long for csv, which stops at 100000s"
+        );
+    }
+
+    fn simulated_points(racers: &[Reindeer], time: u32) -> Vec<u32> {
+        match time {
+            0 => vec![0; racers.len()],
+            t => Race::run(racers, t).at(t).unwrap().points.clone(),
+        }
+    }
+
+    /// Hand-picked racers: exact twins, one who never stops and one who never flies
+    const ODD_HERD: &str = "\
+        Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.\n\
+        Twin can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.\n\
+        Steady can fly 1 km/s for 5 seconds, but then must rest for 0 seconds.\n\
+        Lazy can fly 99 km/s for 0 seconds, but then must rest for 3 seconds.\n\
+        Sprinter can fly 40 km/s for 1 seconds, but then must rest for 9 seconds.";
+
+    #[test]
+    fn scoring_matches_simulation() {
+        let ex = parse_input(EX).unwrap();
+        let input = parse_input(INPUT).unwrap();
+        for time in &[0, 1, 10, 11, 137, 138, 140, 1000, 2503] {
+            assert_eq!(
+                scoring::lead_points(&ex, *time),
+                simulated_points(&ex, *time)
+            );
+            assert_eq!(
+                scoring::lead_points(&input, *time),
+                simulated_points(&input, *time)
+            );
+        }
+        let odd = parse_input(ODD_HERD).unwrap();
+        for time in &[0, 1, 10, 137, 3000] {
+            assert_eq!(
+                scoring::lead_points(&odd, *time),
+                simulated_points(&odd, *time)
+            );
+        }
+    }
+
+    #[test]
+    fn long_race() {
+        let racers = parse_input(INPUT).unwrap();
+        let time = 10_000_000;
+        let points = scoring::lead_points(&racers, time);
+        // somebody leads every second
+        assert!(points.iter().map(|p| *p as u64).sum::<u64>() >= time as u64);
+        // over a long enough race the best average pace takes nearly every point
+        let winner = (0..racers.len()).max_by_key(|i| points[*i]).unwrap();
+        let furthest = (0..racers.len())
+            .max_by_key(|i| racers[*i].distance(time))
+            .unwrap();
+        assert_eq!(winner, furthest);
+        assert!(points[winner] > time / 10 * 9);
+    }
+
+    /// Run with `cargo test --release bench_score -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_score() {
+        let racers = parse_input(INPUT).unwrap();
+
+        let start = std::time::Instant::now();
+        let simulated = simulated_points(&racers, 1_000_000);
+        let simulated_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let scored = scoring::lead_points(&racers, 1_000_000);
+        let scored_time = start.elapsed();
+        assert_eq!(simulated, scored);
+
+        let start = std::time::Instant::now();
+        let points = scoring::lead_points(&racers, 1_000_000_000);
+        println!(
+            "1e6s simulated: {:?} scored: {:?}, 1e9s scored: {:?} {:?}",
+            simulated_time,
+            scored_time,
+            start.elapsed(),
+            points
+        );
+    }
+
+    #[test]
+    fn leaderboard_text() {
+        let race = Race::run(&parse_input(EX).unwrap(), 140);
+        assert_eq!(
+            race.at(140).unwrap().leaderboard(&race.names),
+            "after 140s:\n  \
+             1. Dancer    176 km   139 pts\n  \
+             2. Comet     182 km     1 pts *\n"
+        );
+    }
+
+    #[test]
+    fn race_csv() {
+        let race = Race::run(&parse_input(EX).unwrap(), 2);
+        assert_eq!(
+            race.to_csv(),
+            "second,name,distance,points,leading\n\
+             1,Comet,14,0,false\n\
+             1,Dancer,16,1,true\n\
+             2,Comet,28,0,false\n\
+             2,Dancer,32,2,true\n"
+        );
+    }
+
+    #[test]
+    fn what_ifs() {
+        let opts = |pairs: &[(&str, &str)]| Options::from_pairs(pairs);
+        assert_eq!(
+            standings(EX, &opts(&[("duration", "1000")])).unwrap(),
+            "Dancer 689\nComet  312"
+        );
+        assert_eq!(
+            standings(EX, &opts(&[("duration", "140"), ("scoring", "distance")])).unwrap(),
+            "Comet  182\nDancer 176"
+        );
+        assert_eq!(
+            standings(EX, &opts(&[("duration", "10"), ("scoring", "top:2")])).unwrap(),
+            "Comet  10\nDancer 10"
+        );
+        assert!(standings(EX, &opts(&[("scoring", "fastest")])).is_err());
+        assert!(race(EX, &opts(&[("duration", "10"), ("at", "11")])).is_err());
+    }
+
+    #[test]
+    fn huge_numbers() {
+        let racers = parse_input(
+            "Slow can fly 4294967295 km/s for 4294967295 seconds, \
+             but then must rest for 4294967295 seconds.",
+        )
+        .unwrap();
+        let r = &racers[0];
+        assert_eq!(r.cycle(), 2 * u32::MAX as u64);
+        assert_eq!(r.distance(u32::MAX), u32::MAX as u64 * u32::MAX as u64);
+        assert_eq!(r.distance(10), 10 * u32::MAX as u64);
+        assert_eq!(r.distance_during_time(u32::MAX - 1), u32::MAX as u64);
+        assert_eq!(r.distance_during_time(u32::MAX), 0);
+        assert_eq!(r.next_switch(0), u32::MAX as u64);
+        assert_eq!(r.next_switch(u32::MAX), 2 * u32::MAX as u64);
+        assert_eq!(scoring::lead_points(&racers, 1000), vec![1000]);
+
+        // too far to be exact as a float
+        let line = "Slow can fly 4294967295 km/s for 4294967295 seconds, \
+                    but then must rest for 4294967295 seconds.";
+        assert_eq!(
+            part1(line, &seconds("4294967295")).unwrap(),
+            "18446744065119617025"
+        );
+    }
+
+    #[test]
+    fn input_errors() {
+        let err = parse_input(
+            "Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.\n\
+             Comet can fly 16 km/s for 11 seconds, but then must rest for 162 seconds.",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "line 2: Comet is already racing on line 1");
+
+        let err = parse_input("Comet can fly 14 km/s.").unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "line 1: unable to parse \"Comet can fly 14 km/s.\": \
+             expected \" km/s for \" after speed at column 17, found \" km/s.\""
+        );
+    }
+
+    #[test]
+    fn race_options() {
+        assert_eq!(parse_timestamps("1, 140,1000").unwrap(), vec![1, 140, 1000]);
+        assert!(parse_timestamps("1,x").is_err());
     }
 }
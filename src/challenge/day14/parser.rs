@@ -0,0 +1,112 @@
+//! Parser for the reindeer sentence
+//!
+//! ```text
+//! <name> can fly <speed> km/s for <fly> seconds, but then must rest for <rest> seconds.
+//! ```
+//!
+//! [`Reindeer`] displays as the same sentence, so a parsed line prints back
+//! unchanged.
+
+use std::fmt;
+
+use anyhow::Result;
+
+use super::Reindeer;
+use crate::challenge::sentence::Cursor;
+
+pub fn parse_line(line: &str) -> Result<Reindeer> {
+    let mut c = Cursor::new(line);
+
+    let name = c.word("name")?;
+    c.literal(" can fly ", "name")?;
+    let speed = c.number("speed")?;
+    c.literal(" km/s for ", "speed")?;
+    let fly = c.number("fly time")?;
+    c.literal(" seconds, but then must rest for ", "fly time")?;
+    let rest = c.number("rest time")?;
+    c.literal(" seconds.", "rest time")?;
+    c.end()?;
+
+    if fly == 0 && rest == 0 {
+        anyhow::bail!("{} has to fly or rest for at least a second", name);
+    }
+
+    Ok(Reindeer {
+        name: name.to_string(),
+        speed,
+        fly,
+        rest,
+    })
+}
+
+impl fmt::Display for Reindeer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} can fly {} km/s for {} seconds, but then must rest for {} seconds.",
+            self.name, self.speed, self.fly, self.rest
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const INPUT: &str = include_str!("../../../input/day14");
+
+    fn err(line: &str) -> String {
+        parse_line(line).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn fields() {
+        let r =
+            parse_line("Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.")
+                .unwrap();
+        assert_eq!(r.name, "Comet");
+        assert_eq!((r.speed, r.fly, r.rest), (14, 10, 127));
+    }
+
+    #[test]
+    fn round_trip() {
+        for line in INPUT.lines() {
+            assert_eq!(parse_line(line).unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            err("Comet can swim 14 km/s for 10 seconds, but then must rest for 127 seconds."),
+            "expected \" can fly \" after name at column 6, found \" can swim 14 km/s for 10 seconds, but then must rest for 127 seconds.\""
+        );
+        assert_eq!(
+            err("Comet can fly fast km/s for 10 seconds, but then must rest for 127 seconds."),
+            "speed \"fast\" at column 15 is not a number: invalid digit found in string"
+        );
+        assert_eq!(
+            err("Comet can fly 14 mph for 10 seconds, but then must rest for 127 seconds."),
+            "expected \" km/s for \" after speed at column 17, found \" mph for 10 seconds, but then must rest for 127 seconds.\""
+        );
+        assert_eq!(
+            err("Comet can fly 14 km/s for 10 seconds, then must rest for 127 seconds."),
+            "expected \" seconds, but then must rest for \" after fly time at column 29, found \" seconds, then must rest for 127 seconds.\""
+        );
+        assert_eq!(
+            err("Comet can fly 14 km/s for 10 seconds, but then must rest for seconds."),
+            "rest time \"seconds\" at column 62 is not a number: invalid digit found in string"
+        );
+        assert_eq!(
+            err("Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds"),
+            "expected \" seconds.\" after rest time at column 65, found \" seconds\""
+        );
+        assert_eq!(
+            err("Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds. Go!"),
+            "unexpected trailing text at column 74: \" Go!\""
+        );
+        assert_eq!(
+            err("Comet can fly 14 km/s for 0 seconds, but then must rest for 0 seconds."),
+            "Comet has to fly or rest for at least a second"
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Scoring a race without stepping through it second by second
+//!
+//! Nobody changes speed between two consecutive switches from flying to
+//! resting, so each of those stretches is scored in bulk.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use anyhow::{Context, Result};
+
+use super::Reindeer;
+
+/// How a race is won
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scoring {
+    /// Whoever is furthest at the end
+    Distance,
+    /// A point every second for everyone tied for the lead
+    Lead,
+    /// A point every second, shared equally by everyone tied for the lead
+    Split,
+    /// A point every second for everyone with fewer than N racers ahead of them
+    Top(usize),
+}
+
+impl FromStr for Scoring {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "distance" => Ok(Scoring::Distance),
+            "lead" => Ok(Scoring::Lead),
+            "split" => Ok(Scoring::Split),
+            _ => match s.strip_prefix("top:") {
+                Some(n) => {
+                    let n = n.parse().context("top:N needs a number of places")?;
+                    if n == 0 {
+                        anyhow::bail!("top:N needs at least one place");
+                    }
+                    Ok(Scoring::Top(n))
+                }
+                None => anyhow::bail!("expected `distance`, `lead`, `split` or `top:N`"),
+            },
+        }
+    }
+}
+
+/// A racer's score, exact unless it holds a share of a split point
+#[derive(Debug, Clone, Copy)]
+pub enum Score {
+    Whole(u64),
+    Fraction(f64),
+}
+
+impl Score {
+    fn approx(self) -> f64 {
+        match self {
+            Score::Whole(n) => n as f64,
+            Score::Fraction(f) => f,
+        }
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Score) -> Ordering {
+        match (self, other) {
+            (Score::Whole(a), Score::Whole(b)) => a.cmp(b),
+            (a, b) => a.approx().total_cmp(&b.approx()),
+        }
+    }
+}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Score) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Score) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+/// Whole numbers as they are, fractions of a split point to 3 places
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Whole(n) => write!(f, "{}", n),
+            Score::Fraction(x) if x.fract() == 0.0 => write!(f, "{}", x),
+            Score::Fraction(x) => write!(f, "{:.3}", x),
+        }
+    }
+}
+
+/// Each racer's score after `time` seconds
+pub fn scores(racers: &[Reindeer], time: u32, scoring: Scoring) -> Result<Vec<Score>> {
+    match scoring {
+        Scoring::Distance => Ok(racers
+            .iter()
+            .map(|r| Score::Whole(r.distance(time)))
+            .collect()),
+        Scoring::Lead => Ok(lead_points(racers, time)
+            .into_iter()
+            .map(|p| Score::Whole(p as u64))
+            .collect()),
+        Scoring::Split | Scoring::Top(_) => ranked_points(racers, time, scoring),
+    }
+}
+
+/// Call `f` with the starting distances and speeds of every stretch of
+/// constant speed, and its length in seconds
+fn for_each_stretch<F>(racers: &[Reindeer], time: u32, mut f: F)
+where
+    F: FnMut(&[u64], &[u64], u32),
+{
+    let mut start = vec![0; racers.len()];
+    let mut speeds = racers
+        .iter()
+        .map(|r| r.distance_during_time(0))
+        .collect::<Vec<_>>();
+    let mut switches = racers.iter().map(|r| r.next_switch(0)).collect::<Vec<_>>();
+    let mut now = 0;
+    while now < time {
+        let next = switches
+            .iter()
+            .min()
+            .map_or(time, |t| std::cmp::min(*t, time as u64) as u32);
+        f(&start, &speeds, next - now);
+        for (i, r) in racers.iter().enumerate() {
+            start[i] += speeds[i] * (next - now) as u64;
+            if switches[i] == next as u64 {
+                debug_assert_eq!(start[i], r.distance(next));
+                speeds[i] = r.distance_during_time(next);
+                switches[i] = r.next_switch(next);
+            }
+        }
+        now = next;
+    }
+}
+
+/// Points for each racer after `time` seconds, a point per second in the lead
+pub fn lead_points(racers: &[Reindeer], time: u32) -> Vec<u32> {
+    let mut points = vec![0u32; racers.len()];
+    for_each_stretch(racers, time, |start, speeds, len| {
+        lead_stretch(start, speeds, len, &mut points)
+    });
+    points
+}
+
+/// Award points for `len` seconds where everyone holds a constant speed
+///
+/// After the leaders for one second are known, only the fastest of them can
+/// still be leading a second later, and they stay alone in front until the
+/// first faster racer catches up. Those seconds are awarded all at once.
+fn lead_stretch(start: &[u64], speeds: &[u64], len: u32, points: &mut [u32]) {
+    let len = len as u64;
+    let at = |i: usize, k: u64| start[i] + speeds[i] * k;
+    let mut k = 1;
+    while k <= len {
+        let best = (0..start.len()).map(|i| at(i, k)).max().unwrap_or(0);
+        let leading = |i: &usize| at(*i, k) == best;
+        let pace = (0..start.len())
+            .filter(leading)
+            .map(|i| speeds[i])
+            .max()
+            .unwrap_or(0);
+        for i in (0..start.len()).filter(leading) {
+            points[i] += 1;
+        }
+
+        // the first second someone outside the lead could draw level
+        let caught = (0..start.len())
+            .filter(|i| speeds[*i] > pace)
+            .map(|i| {
+                let gap = best - at(i, k);
+                let gain = speeds[i] - pace;
+                k + gap.div_ceil(gain)
+            })
+            .min();
+        let until = match caught {
+            Some(c) if c <= len => c,
+            _ => len + 1,
+        };
+        for i in (0..start.len()).filter(|i| leading(i) && speeds[*i] == pace) {
+            points[i] += (until - k - 1) as u32;
+        }
+        k = until;
+    }
+}
+
+/// Points for scoring rules that depend on the whole order, not just the lead
+///
+/// Points are counted in shares so that a tie split any number of ways stays
+/// exact: for [`Scoring::Split`] one point is `lcm(1..=racers)` shares, and
+/// since nothing else splits a point, it is one share otherwise.
+fn ranked_points(racers: &[Reindeer], time: u32, scoring: Scoring) -> Result<Vec<Score>> {
+    let per_point = match scoring {
+        Scoring::Split => (1..=racers.len() as u128)
+            .try_fold(1u128, |acc, n| (acc / gcd(acc, n)).checked_mul(n))
+            .filter(|p| p.checked_mul(time as u128).is_some())
+            .context("too many racers to split points exactly")?,
+        _ => 1,
+    };
+    let mut shares = vec![0u128; racers.len()];
+    let mut order = Vec::with_capacity(racers.len());
+    for_each_stretch(racers, time, |start, speeds, len| {
+        for_each_order(start, speeds, len, &mut order, |distances, seconds| {
+            award(distances, scoring, per_point * seconds as u128, &mut shares)
+        })
+    });
+    Ok(shares
+        .into_iter()
+        .map(|s| match per_point {
+            1 => Score::Whole(s as u64),
+            p => Score::Fraction(s as f64 / p as f64),
+        })
+        .collect())
+}
+
+/// Split a stretch of `len` seconds wherever two racers could swap places,
+/// calling `f` with the distances at the start of each piece and its length
+fn for_each_order<F>(start: &[u64], speeds: &[u64], len: u32, cuts: &mut Vec<u64>, mut f: F)
+where
+    F: FnMut(&[u64], u64),
+{
+    let len = len as u64;
+    cuts.clear();
+    cuts.extend(&[1, len + 1]);
+    for i in 0..start.len() {
+        for j in i + 1..start.len() {
+            // the gap between i and j is `a + b * k` after k seconds
+            let a = start[i] as i128 - start[j] as i128;
+            let b = speeds[i] as i128 - speeds[j] as i128;
+            if b == 0 {
+                continue;
+            }
+            // the gap is zero somewhere in `root..root + 1`
+            let root = if b > 0 {
+                (-a).div_euclid(b)
+            } else {
+                a.div_euclid(-b)
+            };
+            for k in [root, root + 1] {
+                if k >= 1 && k <= len as i128 {
+                    cuts.push(k as u64);
+                }
+            }
+        }
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut distances = vec![0; start.len()];
+    for w in cuts.windows(2) {
+        for (i, d) in distances.iter_mut().enumerate() {
+            *d = start[i] + speeds[i] * w[0];
+        }
+        f(&distances, w[1] - w[0]);
+    }
+}
+
+/// Hand out `shares` worth of points for one standing
+fn award(distances: &[u64], scoring: Scoring, shares: u128, totals: &mut [u128]) {
+    let best = distances.iter().max().cloned().unwrap_or(0);
+    match scoring {
+        Scoring::Split => {
+            let leaders = distances.iter().filter(|d| **d == best).count() as u128;
+            for (i, d) in distances.iter().enumerate() {
+                if *d == best {
+                    totals[i] += shares / leaders;
+                }
+            }
+        }
+        Scoring::Top(n) => {
+            for (i, d) in distances.iter().enumerate() {
+                if distances.iter().filter(|other| *other > d).count() < n {
+                    totals[i] += shares;
+                }
+            }
+        }
+        Scoring::Distance | Scoring::Lead => unreachable!("not scored by rank"),
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    match b {
+        0 => a,
+        b => gcd(b, a % b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::day14::parse_input;
+    const EX: &str = include_str!("../../../input/day14_ex");
+    const INPUT: &str = include_str!("../../../input/day14");
+
+    /// Score one second at a time from the closed form distances
+    fn naive(racers: &[Reindeer], time: u32, scoring: Scoring) -> Vec<f64> {
+        let mut totals = vec![0.0; racers.len()];
+        for t in 1..=time {
+            let d = racers.iter().map(|r| r.distance(t)).collect::<Vec<_>>();
+            let best = d.iter().max().cloned().unwrap_or(0);
+            let leaders = d.iter().filter(|x| **x == best).count() as f64;
+            for i in 0..racers.len() {
+                let ahead = d.iter().filter(|x| **x > d[i]).count();
+                totals[i] += match scoring {
+                    Scoring::Lead if ahead == 0 => 1.0,
+                    Scoring::Split if ahead == 0 => 1.0 / leaders,
+                    Scoring::Top(n) if ahead < n => 1.0,
+                    _ => 0.0,
+                };
+            }
+        }
+        totals
+    }
+
+    fn assert_close(a: &[Score], b: &[f64]) {
+        let a = a.iter().map(|s| s.approx()).collect::<Vec<_>>();
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn top_in_a_big_herd() {
+        // lcm(1..=120) does not fit in a u128, but top:N never splits a point
+        let herd = (0..120)
+            .map(|i| Reindeer {
+                name: format!("R{}", i),
+                speed: 10,
+                fly: 5,
+                rest: 5,
+            })
+            .collect::<Vec<_>>();
+        let top = scores(&herd, 1000, Scoring::Top(3)).unwrap();
+        assert!(top.iter().all(|s| *s == Score::Whole(1000)));
+        assert_eq!(
+            scores(&herd, 1000, Scoring::Split).unwrap_err().to_string(),
+            "too many racers to split points exactly"
+        );
+    }
+
+    #[test]
+    fn parse_scoring() {
+        assert_eq!("distance".parse::<Scoring>().unwrap(), Scoring::Distance);
+        assert_eq!("split".parse::<Scoring>().unwrap(), Scoring::Split);
+        assert_eq!("top:3".parse::<Scoring>().unwrap(), Scoring::Top(3));
+        assert!("top:0".parse::<Scoring>().is_err());
+        assert!("top:x".parse::<Scoring>().is_err());
+        assert!("fastest".parse::<Scoring>().is_err());
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut herds = vec![parse_input(EX).unwrap(), parse_input(INPUT).unwrap()];
+        // identical twins tie all race long
+        let mut twins = parse_input(EX).unwrap();
+        twins.push(twins[0].clone());
+        herds.push(twins);
+        for racers in &herds {
+            for scoring in &[
+                Scoring::Lead,
+                Scoring::Split,
+                Scoring::Top(1),
+                Scoring::Top(2),
+                Scoring::Top(4),
+            ] {
+                for time in &[0, 1, 140, 1000] {
+                    let fast = scores(racers, *time, *scoring).unwrap();
+                    assert_close(&fast, &naive(racers, *time, *scoring));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn points_per_second() {
+        let racers = parse_input(INPUT).unwrap();
+        let total = |s| {
+            scores(&racers, 2503, s)
+                .unwrap()
+                .iter()
+                .map(|s| s.approx())
+                .sum::<f64>()
+        };
+        assert!((total(Scoring::Split) - 2503.0).abs() < 1e-6);
+        assert!(total(Scoring::Lead) >= 2503.0);
+        assert_eq!(
+            total(Scoring::Top(racers.len())),
+            2503.0 * racers.len() as f64
+        );
+        assert_eq!(
+            scores(&racers, 2503, Scoring::Distance).unwrap(),
+            racers
+                .iter()
+                .map(|r| Score::Whole(r.distance(2503)))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn show_scores() {
+        assert_eq!(Score::Whole(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Score::Fraction(3.0).to_string(), "3");
+        assert_eq!(Score::Fraction(2.0 / 3.0).to_string(), "0.667");
+        assert!(Score::Whole(u64::MAX) > Score::Whole(u64::MAX - 1));
+        assert!(Score::Fraction(1.5) > Score::Fraction(1.25));
+    }
+}
@@ -0,0 +1,126 @@
+//! Ways of working out how much packaging a box needs
+
+use anyhow::Result;
+
+use super::Box;
+
+/// How much of one kind of packaging a box needs
+pub trait Formula {
+    /// Name used to pick the formula with `formula=NAME`
+    fn name(&self) -> &'static str;
+    /// `None` when the amount doesn't fit in a `usize`
+    fn cost(&self, b: &Box) -> Option<usize>;
+}
+
+/// Wrapping paper: every face, plus the smallest face again as slack (part 1)
+pub struct Paper;
+
+impl Formula for Paper {
+    fn name(&self) -> &'static str {
+        "paper"
+    }
+    fn cost(&self, b: &Box) -> Option<usize> {
+        b.surface_area()?.checked_add(b.smallest_side()?)
+    }
+}
+
+/// Ribbon around the smallest perimeter, plus a bow as long as the volume (part 2)
+pub struct Ribbon;
+
+impl Formula for Ribbon {
+    fn name(&self) -> &'static str {
+        "ribbon"
+    }
+    fn cost(&self, b: &Box) -> Option<usize> {
+        b.smallest_perimeter()?.checked_add(b.volume()?)
+    }
+}
+
+/// A paper gift bag: the box stands on its smallest face and the bag has no top
+pub struct GiftBag;
+
+impl Formula for GiftBag {
+    fn name(&self) -> &'static str {
+        "bag"
+    }
+    fn cost(&self, b: &Box) -> Option<usize> {
+        Some(b.surface_area()? - b.smallest_side()?)
+    }
+}
+
+/// A cardboard tube as long as the box, wide enough for its smallest face
+/// across the diagonal, with both ends capped, rounded up to whole units
+pub struct Tube;
+
+impl Formula for Tube {
+    fn name(&self) -> &'static str {
+        "tube"
+    }
+    fn cost(&self, b: &Box) -> Option<usize> {
+        let (length, across) = b.dims.split_last().expect("a box has dimensions");
+        let diameter = across
+            .iter()
+            .map(|d| (*d as f64).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        let side = std::f64::consts::PI * diameter * *length as f64;
+        let ends = std::f64::consts::PI * diameter * diameter / 2.0;
+        let cost = (side + ends).ceil();
+        // `usize::MAX as f64` rounds up to 2^64, so anything smaller fits
+        if cost < usize::MAX as f64 {
+            Some(cost as usize)
+        } else {
+            None
+        }
+    }
+}
+
+const FORMULAS: &[&dyn Formula] = &[&Paper, &Ribbon, &GiftBag, &Tube];
+
+pub fn by_name(name: &str) -> Result<&'static dyn Formula> {
+    match FORMULAS.iter().find(|f| f.name() == name) {
+        Some(f) => Ok(*f),
+        None => {
+            let names = FORMULAS.iter().map(|f| f.name()).collect::<Vec<_>>();
+            anyhow::bail!(
+                "unknown formula {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples() {
+        let b = Box::new(vec![2, 3, 4]);
+        assert_eq!(Paper.cost(&b), Some(58));
+        assert_eq!(Ribbon.cost(&b), Some(34));
+        assert_eq!(GiftBag.cost(&b), Some(46));
+        // diameter sqrt(13), pi * sqrt(13) * 4 + pi * 13 / 2 = 65.73
+        assert_eq!(Tube.cost(&b), Some(66));
+    }
+
+    #[test]
+    fn too_large() {
+        let b = Box::new(vec![usize::MAX, 2, 2]);
+        for f in FORMULAS {
+            assert_eq!(f.cost(&b), None, "{}", f.name());
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        for f in FORMULAS {
+            assert_eq!(by_name(f.name()).unwrap().name(), f.name());
+        }
+        assert_eq!(
+            by_name("foil").err().unwrap().to_string(),
+            "unknown formula \"foil\", expected one of paper, ribbon, bag, tube"
+        );
+    }
+}
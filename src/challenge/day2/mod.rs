@@ -1,81 +1,143 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use anyhow::{anyhow as ah, Context, Result};
 
+use super::Options;
+
+mod formula;
+mod report;
+
+use formula::Formula;
+
+/// Boxes in the puzzle input are ordinary three dimensional ones
+const DIMENSIONS: usize = 3;
+
 pub fn part1(input: &str) -> Result<String> {
-    let total = box_calc(input, |b| b.surface_area() + b.smallest_side())?;
+    let total = box_calc(&parse_orders(input, DIMENSIONS)?, &formula::Paper)?;
     Ok(format!("{:?}", total))
 }
 
 pub fn part2(input: &str) -> Result<String> {
-    let total = box_calc(input, |b| b.smallest_perimeter() + b.volume())?;
+    let total = box_calc(&parse_orders(input, DIMENSIONS)?, &formula::Ribbon)?;
     Ok(format!("{:?}", total))
 }
 
-fn box_calc<F>(input: &str, f: F) -> Result<usize>
-where
-    F: Fn(&Box) -> usize,
-{
-    let boxes = parse_orders(input)?;
-    let mut total = 0;
-    for b in &boxes {
-        let box_cost = f(b);
-        total += box_cost;
+/// Boxes are numbered by input line, so errors can name the line
+fn box_calc(boxes: &[Box], f: &dyn Formula) -> Result<usize> {
+    let mut total = 0usize;
+    for (idx, b) in boxes.iter().enumerate() {
+        let box_cost = f
+            .cost(b)
+            .ok_or_else(|| ah!("line {}: {} is too large for {}", idx + 1, b, f.name()))?;
+        total = total
+            .checked_add(box_cost)
+            .ok_or_else(|| ah!("line {}: total {} overflows", idx + 1, f.name()))?;
         log::trace!("{:?} -> {} ({})", b, box_cost, total);
     }
     Ok(total)
 }
 
-#[derive(Debug)]
+/// Number of dimensions from `dimensions=N`, defaulting to 3
+fn dimensions(opts: &Options) -> Result<usize> {
+    Ok(opts.get("dimensions")?.unwrap_or(DIMENSIONS))
+}
+
+/// Total of one packaging formula over every box
+///
+/// Options: `formula=paper|ribbon|bag|tube` (default `paper`) and `dimensions=N`
+pub fn cost(input: &str, opts: &Options) -> Result<String> {
+    let f = formula::by_name(opts.get_str("formula").unwrap_or("paper"))?;
+    let total = box_calc(&parse_orders(input, dimensions(opts)?)?, f)?;
+    Ok(format!("{:?}", total))
+}
+
+/// Paper and ribbon for every box, with totals
+///
+/// Options: `format=text|csv` and `dimensions=N`
+pub fn report(input: &str, opts: &Options) -> Result<String> {
+    report::report(&parse_orders(input, dimensions(opts)?)?, opts.format()?)
+}
+
+/// A box with any number of dimensions, at least two
+#[derive(Debug, Clone, PartialEq)]
 struct Box {
-    length: usize,
-    width: usize,
-    height: usize,
+    /// Smallest first
+    dims: Vec<usize>,
 }
 
 impl Box {
-    fn surface_area(&self) -> usize {
-        2 * self.length * self.width + 2 * self.width * self.height + 2 * self.height * self.length
+    fn new(mut dims: Vec<usize>) -> Box {
+        debug_assert!(dims.len() >= 2);
+        dims.sort_unstable();
+        Box { dims }
     }
-    fn volume(&self) -> usize {
-        self.length * self.width * self.height
+
+    /// Each face leaves out one dimension, largest face first
+    fn faces(&self) -> impl Iterator<Item = Option<usize>> + '_ {
+        (0..self.dims.len()).map(move |skip| {
+            product(
+                self.dims
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != skip)
+                    .map(|(_, d)| d),
+            )
+        })
+    }
+
+    // Measurements are `None` when they don't fit in a `usize`
+    fn surface_area(&self) -> Option<usize> {
+        self.faces()
+            .try_fold(0usize, |sum, face| sum.checked_add(face?))?
+            .checked_mul(2)
     }
-    fn smallest_side(&self) -> usize {
-        let a = self.length * self.width;
-        let b = self.width * self.height;
-        let c = self.height * self.length;
-        min3(a, b, c)
+    fn volume(&self) -> Option<usize> {
+        product(self.dims.iter())
     }
-    fn smallest_perimeter(&self) -> usize {
-        let a = 2 * self.length + 2 * self.width;
-        let b = 2 * self.width + 2 * self.height;
-        let c = 2 * self.height + 2 * self.length;
-        min3(a, b, c)
+    fn smallest_side(&self) -> Option<usize> {
+        product(self.dims[..self.dims.len() - 1].iter())
     }
+    /// Around the two shortest edges
+    fn smallest_perimeter(&self) -> Option<usize> {
+        self.dims[0].checked_add(self.dims[1])?.checked_mul(2)
+    }
+}
+
+fn product<'a>(mut dims: impl Iterator<Item = &'a usize>) -> Option<usize> {
+    dims.try_fold(1usize, |p, d| p.checked_mul(*d))
 }
 
-fn min3(a: usize, b: usize, c: usize) -> usize {
-    std::cmp::min(a, std::cmp::min(b, c))
+impl fmt::Display for Box {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dims = self.dims.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", dims.join("x"))
+    }
 }
 
-fn parse_orders(s: &str) -> Result<Vec<Box>> {
+fn parse_orders(s: &str, dimensions: usize) -> Result<Vec<Box>> {
+    if dimensions < 2 {
+        anyhow::bail!("a box needs at least 2 dimensions, not {}", dimensions);
+    }
     s.lines()
-        .map(|order_line| {
-            parse_box(order_line).with_context(|| format!("failed to parse: {}", order_line))
+        .enumerate()
+        .map(|(idx, order_line)| {
+            parse_box(order_line, dimensions)
+                .with_context(|| format!("line {}: failed to parse: {}", idx + 1, order_line))
         })
         .collect::<Result<Vec<Box>>>()
 }
 
-fn parse_box(s: &str) -> Result<Box> {
-    let mut sp = s.split('x');
-    Ok(Box {
-        length: parse_dimm(sp.next())?,
-        width: parse_dimm(sp.next())?,
-        height: parse_dimm(sp.next())?,
-    })
+fn parse_box(s: &str, dimensions: usize) -> Result<Box> {
+    let dims = s.split('x').map(parse_dimm).collect::<Result<Vec<_>>>()?;
+    if dims.len() != dimensions {
+        anyhow::bail!("expected {} dimensions, found {}", dimensions, dims.len());
+    }
+    Ok(Box::new(dims))
 }
-fn parse_dimm(chunk: Option<&str>) -> Result<usize> {
-    let s = chunk.ok_or_else(|| ah!("empty dimmension"))?;
+fn parse_dimm(s: &str) -> Result<usize> {
+    if s.is_empty() {
+        return Err(ah!("empty dimmension"));
+    }
     usize::from_str(s).map_err(|e| ah!("{}", e))
 }
 
@@ -92,4 +154,83 @@ mod tests {
     fn verify_p2() {
         assert_eq!(part2(INPUT).unwrap().as_str(), "3783758")
     }
+
+    #[test]
+    fn field_count() {
+        let err = |s| format!("{:#}", parse_orders(s, 3).unwrap_err());
+        assert_eq!(
+            err("2x3x4\n2x3x4x5"),
+            "line 2: failed to parse: 2x3x4x5: expected 3 dimensions, found 4"
+        );
+        assert_eq!(
+            err("2x3"),
+            "line 1: failed to parse: 2x3: expected 3 dimensions, found 2"
+        );
+        assert_eq!(
+            err("2x3x"),
+            "line 1: failed to parse: 2x3x: empty dimmension"
+        );
+        assert!(parse_orders("2", 1).is_err());
+    }
+
+    #[test]
+    fn sorted_dimensions() {
+        let b = parse_box("4x2x3", 3).unwrap();
+        assert_eq!(b.dims, vec![2, 3, 4]);
+        assert_eq!(b.to_string(), "2x3x4");
+        assert_eq!(b.faces().collect::<Option<Vec<_>>>(), Some(vec![12, 8, 6]));
+    }
+
+    #[test]
+    fn more_dimensions() {
+        let b = parse_box("1x2x3x4", 4).unwrap();
+        assert_eq!(b.surface_area(), Some(2 * (24 + 12 + 8 + 6)));
+        assert_eq!(b.smallest_side(), Some(6));
+        assert_eq!(b.smallest_perimeter(), Some(6));
+        assert_eq!(b.volume(), Some(24));
+
+        let square = parse_box("3x5", 2).unwrap();
+        assert_eq!(square.surface_area(), Some(16));
+        assert_eq!(square.smallest_side(), Some(3));
+    }
+
+    #[test]
+    fn cost_options() {
+        let opts = |pairs: &[(&str, &str)]| Options::from_pairs(pairs);
+        assert_eq!(cost("2x3x4", &opts(&[])).unwrap(), "58");
+        assert_eq!(
+            cost("2x3x4", &opts(&[("formula", "ribbon")])).unwrap(),
+            "34"
+        );
+        assert_eq!(
+            cost(
+                "1x2x3x4",
+                &opts(&[("dimensions", "4"), ("formula", "ribbon")])
+            )
+            .unwrap(),
+            "30"
+        );
+        assert!(cost("2x3x4", &opts(&[("formula", "foil")])).is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        let err = |s: &str| format!("{:#}", part1(s).unwrap_err());
+        let huge = format!("2x3x4\n2x2x{}", usize::MAX);
+        assert_eq!(
+            err(&huge),
+            format!("line 2: 2x2x{} is too large for paper", usize::MAX)
+        );
+        let half = usize::MAX / 2;
+        let costly = format!("{}x1x1\n{}x1x1", half, half);
+        assert_eq!(
+            format!("{:#}", part2(&costly).unwrap_err()),
+            "line 2: total ribbon overflows"
+        );
+        // 2^64, but every face is only 2^48
+        let fat = "65536x65536x65536x65536";
+        let opts = |f| Options::from_pairs(&[("dimensions", "4"), ("formula", f)]);
+        assert!(cost(fat, &opts("paper")).is_ok());
+        assert!(cost(fat, &opts("ribbon")).is_err());
+    }
 }
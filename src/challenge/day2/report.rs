@@ -0,0 +1,278 @@
+//! Everything each box needs, as a table or CSV, with totals at the end
+
+use std::fmt::Write;
+
+use anyhow::{anyhow as ah, Result};
+
+use super::{
+    formula::{Formula, Paper, Ribbon},
+    Box,
+};
+use crate::challenge::options::Format;
+
+/// Paper and ribbon for one box
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    dims: String,
+    area: usize,
+    /// Extra paper, the area of the smallest side
+    slack: usize,
+    /// Ribbon around the smallest perimeter
+    ribbon: usize,
+    /// Ribbon for the bow, as long as the volume
+    bow: usize,
+    volume: usize,
+    /// Total paper, from [`Paper`]
+    paper: usize,
+    /// Total ribbon, from [`Ribbon`]
+    total_ribbon: usize,
+}
+
+impl Row {
+    /// `None` if any amount, or the total cost, doesn't fit in a `usize`
+    fn new(b: &Box) -> Option<Row> {
+        let row = Row {
+            dims: b.to_string(),
+            area: b.surface_area()?,
+            slack: b.smallest_side()?,
+            ribbon: b.smallest_perimeter()?,
+            bow: b.volume()?,
+            volume: b.volume()?,
+            paper: Paper.cost(b)?,
+            total_ribbon: Ribbon.cost(b)?,
+        };
+        row.paper.checked_add(row.total_ribbon)?;
+        Some(row)
+    }
+
+    /// Paper and ribbon together
+    fn cost(&self) -> usize {
+        self.paper + self.total_ribbon
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Summary {
+    boxes: usize,
+    /// The box with the largest volume, first one wins a tie
+    largest: Option<String>,
+    median_cost: Option<f64>,
+    paper: usize,
+    ribbon: usize,
+}
+
+impl Summary {
+    fn new(rows: &[Row]) -> Result<Summary> {
+        let mut costs = rows.iter().map(Row::cost).collect::<Vec<_>>();
+        costs.sort_unstable();
+        let median_cost = match costs.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(costs[n / 2] as f64),
+            n => Some((costs[n / 2 - 1] as f64 + costs[n / 2] as f64) / 2.0),
+        };
+        let largest = rows
+            .iter()
+            .rev()
+            .max_by_key(|r| r.volume)
+            .map(|r| r.dims.clone());
+        let total = |name, amount: fn(&Row) -> usize| {
+            rows.iter()
+                .try_fold(0usize, |sum, r| sum.checked_add(amount(r)))
+                .ok_or_else(|| ah!("total {} overflows", name))
+        };
+        Ok(Summary {
+            boxes: rows.len(),
+            largest,
+            median_cost,
+            paper: total("paper", |r| r.paper)?,
+            ribbon: total("ribbon", |r| r.total_ribbon)?,
+        })
+    }
+
+    fn lines(&self) -> Vec<(&'static str, String)> {
+        let or_none = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        vec![
+            ("boxes", self.boxes.to_string()),
+            ("largest box", or_none(self.largest.clone())),
+            (
+                "median cost",
+                or_none(self.median_cost.map(|m| m.to_string())),
+            ),
+            ("total paper", self.paper.to_string()),
+            ("total ribbon", self.ribbon.to_string()),
+            ("paper and ribbon", (self.paper + self.ribbon).to_string()),
+        ]
+    }
+}
+
+const HEADERS: [&str; 9] = [
+    "box",
+    "area",
+    "slack",
+    "paper",
+    "ribbon",
+    "bow",
+    "volume",
+    "total ribbon",
+    "cost",
+];
+
+fn fields(r: &Row) -> [String; 9] {
+    [
+        r.dims.clone(),
+        r.area.to_string(),
+        r.slack.to_string(),
+        r.paper.to_string(),
+        r.ribbon.to_string(),
+        r.bow.to_string(),
+        r.volume.to_string(),
+        r.total_ribbon.to_string(),
+        r.cost().to_string(),
+    ]
+}
+
+pub fn report(boxes: &[Box], format: Format) -> Result<String> {
+    let rows = boxes
+        .iter()
+        .enumerate()
+        .map(|(idx, b)| Row::new(b).ok_or_else(|| ah!("line {}: {} is too large", idx + 1, b)))
+        .collect::<Result<Vec<_>>>()?;
+    let summary = Summary::new(&rows)?;
+    match format {
+        Format::Text => Ok(table(&rows, &summary)),
+        Format::Csv => Ok(csv(&rows, &summary)),
+        Format::Json => anyhow::bail!("the report can only be shown as text or csv"),
+    }
+}
+
+fn table(rows: &[Row], summary: &Summary) -> String {
+    let cells = rows.iter().map(fields).collect::<Vec<_>>();
+    let widths = (0..HEADERS.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|c| c[i].len())
+                .chain(std::iter::once(HEADERS[i].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    let line = |out: &mut String, row: &[&str]| {
+        let cols = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (c, w))| match i {
+                0 => format!("{:<w$}", c, w = w),
+                _ => format!("{:>w$}", c, w = w),
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "{}", cols.join("  ").trim_end()).expect("writing to a string");
+    };
+    line(&mut out, &HEADERS);
+    for c in &cells {
+        line(&mut out, &c.iter().map(|s| s.as_str()).collect::<Vec<_>>());
+    }
+
+    out.push('\n');
+    let summary = summary.lines();
+    let label_width = summary.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+    for (label, value) in summary {
+        writeln!(out, "{:<w$}  {}", label, value, w = label_width).expect("writing to a string");
+    }
+    out.trim_end().to_string()
+}
+
+/// One line per box, then the summary as `# label,value` comments
+fn csv(rows: &[Row], summary: &Summary) -> String {
+    let mut out = HEADERS.join(",");
+    out.push('\n');
+    for r in rows {
+        writeln!(out, "{}", fields(r).join(",")).expect("writing to a string");
+    }
+    for (label, value) in summary.lines() {
+        writeln!(out, "# {},{}", label, value).expect("writing to a string");
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::day2::parse_orders;
+
+    #[test]
+    fn example_rows() {
+        let rows = parse_orders("2x3x4\n1x1x10", 3)
+            .unwrap()
+            .iter()
+            .map(|b| Row::new(b).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!((rows[0].paper, rows[0].total_ribbon), (58, 34));
+        assert_eq!((rows[1].paper, rows[1].total_ribbon), (43, 14));
+
+        let summary = Summary::new(&rows).unwrap();
+        assert_eq!(summary.largest.as_deref(), Some("2x3x4"));
+        assert_eq!(summary.median_cost, Some(74.5));
+        assert_eq!((summary.paper, summary.ribbon), (101, 48));
+    }
+
+    #[test]
+    fn empty_order() {
+        let summary = Summary::new(&[]).unwrap();
+        assert_eq!(summary.largest, None);
+        assert_eq!(summary.median_cost, None);
+        assert!(report(&[], Format::Json).is_err());
+    }
+
+    #[test]
+    fn too_large() {
+        let err = |s: &str| {
+            format!(
+                "{:#}",
+                report(&parse_orders(s, 3).unwrap(), Format::Text).unwrap_err()
+            )
+        };
+        assert_eq!(
+            err(&format!("2x3x4\n1x1x{}", usize::MAX)),
+            format!("line 2: 1x1x{} is too large", usize::MAX)
+        );
+        // Each box costs half the range, mostly in paper
+        let long = format!("1x1x{}\n", usize::MAX / 10);
+        assert_eq!(err(&long.repeat(3)), "total paper overflows");
+    }
+
+    #[test]
+    fn formats() {
+        let boxes = parse_orders("2x3x4\n1x1x10\n1x2x3", 3).unwrap();
+        assert_eq!(
+            report(&boxes, Format::Text).unwrap(),
+            "\
+box     area  slack  paper  ribbon  bow  volume  total ribbon  cost
+2x3x4     52      6     58      10   24      24            34    92
+1x1x10    42      1     43       4   10      10            14    57
+1x2x3     22      2     24       6    6       6            12    36
+
+boxes             3
+largest box       2x3x4
+median cost       57
+total paper       125
+total ribbon      60
+paper and ribbon  185"
+        );
+        assert_eq!(
+            report(&boxes[..1], Format::Csv).unwrap(),
+            "\
+box,area,slack,paper,ribbon,bow,volume,total ribbon,cost
+2x3x4,52,6,58,10,24,24,34,92
+# boxes,1
+# largest box,2x3x4
+# median cost,92
+# total paper,58
+# total ribbon,34
+# paper and ribbon,92"
+        );
+    }
+}
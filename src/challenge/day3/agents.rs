@@ -0,0 +1,205 @@
+//! Any number of Santas and robots sharing one list of instructions
+
+use anyhow::{Context, Result};
+use aoc::grid::compass::Direction;
+
+use super::{Loc, Visits};
+use crate::challenge::Options;
+
+/// Who follows each instruction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dealing {
+    /// Instructions go to each of this many agents in turn
+    RoundRobin(usize),
+    /// A repeating pattern of agent numbers, `0,0,1` gives every third move to agent 1
+    Schedule(Vec<usize>),
+}
+
+impl Dealing {
+    /// Options: `agents=N` (default 1) and `schedule=A,B,...` of agent numbers
+    /// counting from 0. A schedule has to give every agent a turn, up to `N`
+    /// or its highest agent number.
+    pub fn from_options(opts: &Options) -> Result<Dealing> {
+        let agents = opts.get::<usize>("agents")?;
+        let schedule = match opts.get_str("schedule") {
+            Some(s) => s,
+            None => {
+                let n = agents.unwrap_or(1);
+                if n == 0 {
+                    anyhow::bail!("there must be at least one agent");
+                }
+                return Ok(Dealing::RoundRobin(n));
+            }
+        };
+
+        let schedule = schedule
+            .split(',')
+            .map(|a| {
+                a.trim()
+                    .parse::<usize>()
+                    .with_context(|| format!("invalid agent {:?} in schedule", a))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let named = schedule.iter().max().map_or(0, |a| a + 1);
+        let n = agents.unwrap_or(named);
+        if named > n {
+            anyhow::bail!(
+                "schedule names agent {} but there are only {}",
+                named - 1,
+                n
+            );
+        }
+        if let Some(idle) = (0..n).find(|a| !schedule.contains(a)) {
+            anyhow::bail!("agent {} never gets a turn in the schedule", idle);
+        }
+        Ok(Dealing::Schedule(schedule))
+    }
+
+    pub fn agents(&self) -> usize {
+        match self {
+            Dealing::RoundRobin(n) => *n,
+            Dealing::Schedule(s) => s.iter().max().map_or(1, |a| a + 1),
+        }
+    }
+
+    fn agent_for(&self, step: usize) -> usize {
+        match self {
+            Dealing::RoundRobin(n) => step % n,
+            Dealing::Schedule(s) => s[step % s.len()],
+        }
+    }
+}
+
+/// Houses visited by each agent and by all of them together
+#[derive(Debug, Default)]
+pub struct Delivery {
+    pub total: Visits,
+    pub per_agent: Vec<Visits>,
+}
+
+/// Every agent delivers at the origin, then after each of their moves
+pub fn deliver(instructions: &[Direction], dealing: &Dealing) -> Delivery {
+    let n = dealing.agents();
+    let start = Loc::new(0, 0);
+    let mut pos = vec![start; n];
+    let mut d = Delivery {
+        total: Visits::default(),
+        per_agent: (0..n).map(|_| Visits::default()).collect(),
+    };
+    for visits in d.per_agent.iter_mut() {
+        visits.add(start);
+        d.total.add(start);
+    }
+    for (step, dir) in instructions.iter().enumerate() {
+        let a = dealing.agent_for(step);
+        pos[a] = pos[a] + dir.delta();
+        d.per_agent[a].add(pos[a]);
+        d.total.add(pos[a]);
+    }
+    d
+}
+
+impl Delivery {
+    /// Houses visited by more than one agent
+    pub fn shared(&self) -> usize {
+        self.total
+            .houses_iter()
+            .filter(|loc| self.per_agent.iter().filter(|v| v.get(*loc) > 0).count() > 1)
+            .count()
+    }
+
+    /// Houses visited by both agent `a` and agent `b`
+    pub fn overlap(&self, a: usize, b: usize) -> usize {
+        self.per_agent[a]
+            .houses_iter()
+            .filter(|loc| self.per_agent[b].get(*loc) > 0)
+            .count()
+    }
+
+    pub fn report(&self) -> String {
+        let n = self.per_agent.len();
+        let mut lines = vec![
+            format!("agents: {}", n),
+            format!("houses: {}", self.total.houses()),
+        ];
+        for (a, visits) in self.per_agent.iter().enumerate() {
+            lines.push(format!("agent {}: {} houses", a, visits.houses()));
+        }
+        if n > 1 {
+            lines.push(format!("shared by 2 or more: {}", self.shared()));
+            for a in 0..n {
+                for b in a + 1..n {
+                    lines.push(format!("agents {} and {}: {}", a, b, self.overlap(a, b)));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::day3::parse_input;
+
+    fn moves(s: &str) -> Vec<Direction> {
+        parse_input(s, &Options::default()).unwrap()
+    }
+
+    #[test]
+    fn dealing_options() {
+        let dealing = |pairs: &[(&str, &str)]| Dealing::from_options(&Options::from_pairs(pairs));
+        assert_eq!(dealing(&[]).unwrap(), Dealing::RoundRobin(1));
+        assert_eq!(dealing(&[("agents", "3")]).unwrap(), Dealing::RoundRobin(3));
+        assert_eq!(
+            dealing(&[("schedule", "0,0,1")]).unwrap(),
+            Dealing::Schedule(vec![0, 0, 1])
+        );
+        assert!(dealing(&[("agents", "0")]).is_err());
+        assert!(dealing(&[("agents", "2"), ("schedule", "0,2")]).is_err());
+        assert!(dealing(&[("agents", "3"), ("schedule", "0,1")]).is_err());
+        assert!(dealing(&[("schedule", "0,x")]).is_err());
+        assert_eq!(
+            dealing(&[("schedule", "0,2")]).unwrap_err().to_string(),
+            "agent 1 never gets a turn in the schedule"
+        );
+        assert_eq!(
+            dealing(&[("agents", "3"), ("schedule", "0,1")])
+                .unwrap_err()
+                .to_string(),
+            "agent 2 never gets a turn in the schedule"
+        );
+    }
+
+    #[test]
+    fn round_robin_matches_parts() {
+        let m = moves("^v^v^v^v^v");
+        assert_eq!(deliver(&m, &Dealing::RoundRobin(1)).total.houses(), 2);
+        let two = deliver(&m, &Dealing::RoundRobin(2));
+        assert_eq!(two.total.houses(), 11);
+        assert_eq!(two.per_agent[0].houses(), 6);
+        assert_eq!(two.per_agent[1].houses(), 6);
+        // only the origin is shared
+        assert_eq!(two.shared(), 1);
+        assert_eq!(two.overlap(0, 1), 1);
+
+        // a schedule that alternates is the same as two agents in turn
+        let alt = deliver(&m, &Dealing::Schedule(vec![0, 1]));
+        assert_eq!(alt.total.houses(), two.total.houses());
+    }
+
+    #[test]
+    fn uneven_schedule() {
+        // agent 0 gets `>>`, agent 1 the `^`, and so on
+        let m = moves(">>^>>v");
+        let d = deliver(&m, &Dealing::Schedule(vec![0, 0, 1]));
+        assert_eq!(d.per_agent[0].houses(), 5);
+        assert_eq!(d.per_agent[1].houses(), 2);
+        assert_eq!(d.overlap(0, 1), 1);
+        assert_eq!(
+            d.report(),
+            "agents: 2\nhouses: 6\nagent 0: 5 houses\nagent 1: 2 houses\n\
+             shared by 2 or more: 1\nagents 0 and 1: 1"
+        );
+    }
+}
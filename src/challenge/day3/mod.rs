@@ -3,56 +3,139 @@ use std::collections::HashMap;
 use anyhow::Result;
 use aoc::grid::{compass::Direction, point::Point};
 
+use super::{chars::CharStream, Options};
+
+mod agents;
+mod render;
+
+use agents::{deliver, Dealing};
+
 type Loc = Point<i64>;
 
-pub fn part1(input: &str) -> Result<String> {
-    let instructions = parse_input(input)?;
-    let mut count = HashMap::new();
+pub fn part1(input: &str, opts: &Options) -> Result<String> {
+    let instructions = parse_input(input, opts)?;
+    let delivery = deliver(&instructions, &Dealing::RoundRobin(1));
+    Ok(format!("{:?}", delivery.total.houses()))
+}
+
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    let instructions = parse_input(input, opts)?;
+    let delivery = deliver(&instructions, &Dealing::RoundRobin(2));
+    Ok(format!("{:?}", delivery.total.houses()))
+}
+
+/// Houses for each agent and how much their rounds overlap
+///
+/// Options: `agents=N` and `schedule=A,B,...`, see [`Dealing::from_options`]
+pub fn agents(input: &str, opts: &Options) -> Result<String> {
+    let instructions = parse_input(input, opts)?;
+    let dealing = Dealing::from_options(opts)?;
+    Ok(deliver(&instructions, &dealing).report())
+}
+
+/// Statistics about the deliveries and a heatmap of the houses visited
+///
+/// Options: `render=ascii|pgm|none` (default `ascii`), and `agents`/`schedule`
+/// as for [`agents`]. A PGM image goes to stdout on its own, ready to redirect
+/// to a file.
+pub fn map(input: &str, opts: &Options) -> Result<String> {
+    let instructions = parse_input(input, opts)?;
+    let visits = deliver(&instructions, &Dealing::from_options(opts)?).total;
+
+    let render = opts.get_str("render").unwrap_or("ascii");
+    match render {
+        "pgm" => return Ok(render::pgm(&visits)),
+        "ascii" | "none" => {}
+        _ => anyhow::bail!(
+            "unknown render {:?}, expected `ascii`, `pgm` or `none`",
+            render
+        ),
+    }
 
-    for p in houses(instructions.iter()) {
-        let x = count.entry(p).or_insert(0);
-        *x += 1;
+    let mut lines = vec![
+        format!("houses: {}", visits.houses()),
+        format!("visited once: {}", visits.visited_once()),
+    ];
+    if let Some((loc, n)) = visits.most_visited() {
+        lines.push(format!("most visited: ({}, {}) with {}", loc.x, loc.y, n));
     }
-    Ok(format!("{:?}", count.len()))
+    if let Some((lo, hi)) = visits.bounds() {
+        lines.push(format!(
+            "bounds: ({}, {}) to ({}, {}), {}x{}",
+            lo.x,
+            lo.y,
+            hi.x,
+            hi.y,
+            hi.x - lo.x + 1,
+            hi.y - lo.y + 1
+        ));
+    }
+    if render == "ascii" {
+        lines.push(String::new());
+        lines.push(render::ascii(&visits));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// How many presents each house has had
+#[derive(Debug, Default)]
+struct Visits {
+    count: HashMap<Loc, usize>,
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let instructions = parse_input(input)?;
-    let mut count = HashMap::new();
+impl Visits {
+    fn add(&mut self, loc: Loc) {
+        *self.count.entry(loc).or_insert(0) += 1;
+    }
+
+    fn houses(&self) -> usize {
+        self.count.len()
+    }
+
+    fn houses_iter(&self) -> impl Iterator<Item = Loc> + '_ {
+        self.count.keys().cloned()
+    }
+
+    fn get(&self, loc: Loc) -> usize {
+        self.count.get(&loc).cloned().unwrap_or(0)
+    }
+
+    fn visited_once(&self) -> usize {
+        self.count.values().filter(|n| **n == 1).count()
+    }
 
-    for p in houses(instructions.iter().step_by(2)) {
-        let x = count.entry(p).or_insert(0);
-        *x += 1;
+    /// The house with the most presents, preferring the lowest `(y, x)` on a tie
+    fn most_visited(&self) -> Option<(Loc, usize)> {
+        self.count
+            .iter()
+            .max_by_key(|(loc, n)| (**n, std::cmp::Reverse((loc.y, loc.x))))
+            .map(|(loc, n)| (*loc, *n))
     }
-    for p in houses(instructions.iter().skip(1).step_by(2)) {
-        let x = count.entry(p).or_insert(0);
-        *x += 1;
+
+    /// The lowest and highest corners of every visited house
+    fn bounds(&self) -> Option<(Loc, Loc)> {
+        let xs = || self.count.keys().map(|l| l.x);
+        let ys = || self.count.keys().map(|l| l.y);
+        Some((
+            Loc::new(xs().min()?, ys().min()?),
+            Loc::new(xs().max()?, ys().max()?),
+        ))
     }
-    Ok(format!("{:?}", count.len()))
 }
 
-fn parse_input(s: &str) -> Result<Vec<Direction>> {
-    s.chars().map(parse_direction).collect()
+fn parse_input(s: &str, opts: &Options) -> Result<Vec<Direction>> {
+    CharStream::from_options(s, opts)?.decode("a direction", parse_direction)
 }
 
-fn parse_direction(c: char) -> Result<Direction> {
+fn parse_direction(c: char) -> Option<Direction> {
     let d = match c {
         '^' => Direction::North,
         '>' => Direction::East,
         'v' => Direction::South,
         '<' => Direction::West,
-        _ => anyhow::bail!("unknown direction: {:?}", c),
+        _ => return None,
     };
-    Ok(d)
-}
-
-fn houses<'a>(inst: impl Iterator<Item = &'a Direction> + 'a) -> impl Iterator<Item = Loc> + 'a {
-    let mut p = Loc::new(0, 0);
-    inst.map(move |d| {
-        let last = p;
-        p = p + d.delta();
-        last
-    })
+    Some(d)
 }
 
 #[cfg(test)]
@@ -62,10 +145,70 @@ mod tests {
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "2572")
+        assert_eq!(part1(INPUT, &Options::default()).unwrap().as_str(), "2572")
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "2631")
+        assert_eq!(part2(INPUT, &Options::default()).unwrap().as_str(), "2631")
+    }
+
+    #[test]
+    fn examples() {
+        let none = Options::default();
+        assert_eq!(part1(">", &none).unwrap().as_str(), "2");
+        assert_eq!(part1("^>v<", &none).unwrap().as_str(), "4");
+        assert_eq!(part1("^v^v^v^v^v", &none).unwrap().as_str(), "2");
+        assert_eq!(part2("^v", &none).unwrap().as_str(), "3");
+        assert_eq!(part2("^>v<", &none).unwrap().as_str(), "3");
+        assert_eq!(part2("^v^v^v^v^v", &none).unwrap().as_str(), "11");
+    }
+
+    #[test]
+    fn trailing_newline() {
+        let none = Options::default();
+        assert_eq!(part1("^>v<\n", &none).unwrap().as_str(), "4");
+        assert_eq!(
+            part1("^>x", &none).unwrap_err().to_string(),
+            "expected a direction but found 'x' at byte 2"
+        );
+    }
+
+    /// `aoc` puts north towards positive `y`, which every map below relies on
+    #[test]
+    fn north_is_positive_y() {
+        assert_eq!(Direction::North.delta(), Loc::new(0, 1));
+        assert_eq!(Direction::East.delta(), Loc::new(1, 0));
+    }
+
+    #[test]
+    fn statistics() {
+        let moves = parse_input(">>^<<v>", &Options::default()).unwrap();
+        let visits = deliver(&moves, &Dealing::RoundRobin(1)).total;
+        assert_eq!(visits.houses(), 6);
+        assert_eq!(visits.get(Loc::new(0, 0)), 2);
+        assert_eq!(visits.get(Loc::new(1, 0)), 2);
+        assert_eq!(visits.visited_once(), 4);
+        assert_eq!(visits.most_visited(), Some((Loc::new(0, 0), 2)));
+
+        assert_eq!(visits.bounds(), Some((Loc::new(0, 0), Loc::new(2, 1))));
+
+        assert_eq!(Visits::default().most_visited(), None);
+        assert_eq!(Visits::default().bounds(), None);
+    }
+
+    #[test]
+    fn map_report() {
+        let opts = Options::from_pairs(&[("render", "none")]);
+        assert_eq!(
+            map("^>v<", &opts).unwrap(),
+            "houses: 4\nvisited once: 3\nmost visited: (0, 0) with 2\n\
+             bounds: (0, 0) to (1, 1), 2x2"
+        );
+        let opts = Options::from_pairs(&[("render", "svg")]);
+        assert!(map("^", &opts).is_err());
+        let opts = Options::from_pairs(&[("render", "none"), ("agents", "2")]);
+        assert!(map("^v^v^v^v^v", &opts)
+            .unwrap()
+            .starts_with("houses: 11\n"));
     }
 }
@@ -0,0 +1,87 @@
+//! Heatmaps of how many presents each house got, with north at the top
+
+use super::{Loc, Visits};
+
+/// One character per house, from a single present up to nine or more
+const SHADES: &[u8] = b".:-=+*#%@";
+
+/// Rows from the northernmost down, each as a list of present counts
+fn rows(visits: &Visits) -> Vec<Vec<usize>> {
+    let (lo, hi) = match visits.bounds() {
+        Some(b) => b,
+        None => return Vec::new(),
+    };
+    // north is towards positive y, so the top row is the highest
+    (lo.y..=hi.y)
+        .rev()
+        .map(|y| (lo.x..=hi.x).map(|x| visits.get(Loc::new(x, y))).collect())
+        .collect()
+}
+
+pub fn ascii(visits: &Visits) -> String {
+    let lines = rows(visits)
+        .into_iter()
+        .map(|row| {
+            let line = row
+                .into_iter()
+                .map(|n| match n {
+                    0 => ' ',
+                    n => SHADES[std::cmp::min(n, SHADES.len()) - 1] as char,
+                })
+                .collect::<String>();
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>();
+    lines.join("\n")
+}
+
+/// A plain (P2) greyscale image, brighter for more presents
+pub fn pgm(visits: &Visits) -> String {
+    let rows = rows(visits);
+    // a maxval of 0 isn't valid, even for an empty map
+    let most = rows.iter().flatten().max().cloned().unwrap_or(0).max(1);
+    let width = rows.first().map_or(0, |r| r.len());
+    let mut out = format!("P2\n{} {}\n{}\n", width, rows.len(), most);
+    for row in rows {
+        let line = row.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc::grid::compass::Direction;
+
+    use super::*;
+    use crate::challenge::day3::agents::{deliver, Dealing};
+
+    fn visits(moves: &[Direction]) -> Visits {
+        deliver(moves, &Dealing::RoundRobin(1)).total
+    }
+
+    #[test]
+    fn north_is_up() {
+        use Direction::*;
+        let v = visits(&[North, North, East, South, South, South, West, North]);
+        assert_eq!(ascii(&v), "..\n..\n:.\n..");
+        assert_eq!(pgm(&v), "P2\n2 4\n2\n1 1\n1 1\n2 1\n1 1");
+
+        let v = visits(&[North, East, South]);
+        assert_eq!(ascii(&v), "..\n..");
+        let v = visits(&[North]);
+        assert_eq!(ascii(&v), ".\n.");
+    }
+
+    #[test]
+    fn shades() {
+        // seven presents at the origin and six next door
+        let v = visits(&[Direction::East, Direction::West].repeat(6));
+        assert_eq!(ascii(&v), "#*");
+        let v = visits(&[Direction::East, Direction::West].repeat(10));
+        assert_eq!(ascii(&v), "@@");
+        assert_eq!(ascii(&Visits::default()), "");
+        assert_eq!(pgm(&Visits::default()), "P2\n0 0\n1");
+    }
+}
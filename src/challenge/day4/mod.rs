@@ -1,53 +1,253 @@
-use anyhow::Result;
+use anyhow::{anyhow as ah, Result};
 use rayon::prelude::*;
 
-const PART1_MAX: u64 = 2000000;
-const PART2_MAX: u64 = 2000000;
+use super::{progress::Progress, Options};
+
+/// How many nonces an unbounded search checks before moving on to the next chunk
+const CHUNK_SIZE: u64 = 1 << 20;
+
+/// Nonces handed to a thread at a time, checked in order from a single copy of the prefix
+const BLOCK_SIZE: u64 = 4096;
 
 pub fn part1(input: &str) -> Result<String> {
     let input = input.trim();
-    let n = scan(input, PART1_MAX, is_five_zeros)?;
+    let n = Miner::new(input, Target::leading_zeros(5)?).mine()?;
     Ok(format!("{:?}", n))
 }
 
 pub fn part2(input: &str) -> Result<String> {
     let input = input.trim();
-    let n = scan(input, PART2_MAX, is_six_zeros)?;
+    let n = Miner::new(input, Target::leading_zeros(6)?).mine()?;
     Ok(format!("{:?}", n))
 }
 
-fn scan<F>(secret: &str, max: u64, f: F) -> Result<u64>
-where
-    F: Fn(md5::Digest) -> bool + Sync,
-{
-    (0u64..max)
+/// Search with a target and nonce range from the command line
+///
+/// Options: `zeros=N` or `prefix=HEX` (default `zeros=5`), `start=N`, `end=N`
+pub fn mine(input: &str, opts: &Options) -> Result<String> {
+    let input = input.trim();
+    let target = match (opts.get::<usize>("zeros")?, opts.get_str("prefix")) {
+        (Some(_), Some(_)) => anyhow::bail!("only one of `zeros` and `prefix` may be set"),
+        (Some(n), None) => Target::leading_zeros(n)?,
+        (None, Some(p)) => Target::hex_prefix(p)?,
+        (None, None) => Target::leading_zeros(5)?,
+    };
+    let mut miner = Miner::new(input, target);
+    if let Some(start) = opts.get("start")? {
+        miner = miner.start(start);
+    }
+    if let Some(end) = opts.get("end")? {
+        miner = miner.end(end);
+    }
+    let n = miner.mine()?;
+    Ok(format!("{:?}", n))
+}
+
+/// The hex digits a digest has to start with
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    /// Leading bytes that must match exactly
+    bytes: Vec<u8>,
+    /// A trailing odd nibble, compared against the high half of the next byte
+    nibble: Option<u8>,
+}
+
+impl Target {
+    fn leading_zeros(n: usize) -> Result<Target> {
+        Target::hex_prefix(&"0".repeat(n))
+    }
+
+    fn hex_prefix(prefix: &str) -> Result<Target> {
+        if prefix.len() > 32 {
+            anyhow::bail!("md5 digests only have 32 hex digits: {:?}", prefix);
+        }
+        let nibbles = prefix
+            .chars()
+            .map(|c| {
+                c.to_digit(16)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| ah!("invalid hex digit {:?} in prefix {:?}", c, prefix))
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        let chunks = nibbles.chunks_exact(2);
+        let nibble = chunks.remainder().first().cloned();
+        let bytes = chunks.map(|pair| pair[0] << 4 | pair[1]).collect();
+        Ok(Target { bytes, nibble })
+    }
+
+    #[inline]
+    fn matches(&self, digest: md5::Digest) -> bool {
+        let data: [u8; 16] = digest.into();
+        let n = self.bytes.len();
+        if data[..n] != self.bytes[..] {
+            return false;
+        }
+        match self.nibble {
+            Some(h) => data[n] >> 4 == h,
+            None => true,
+        }
+    }
+}
+
+/// The prefix as hex digits, as it would be typed for `prefix=HEX`
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for b in &self.bytes {
+            write!(f, "{:02x}", b)?;
+        }
+        if let Some(h) = self.nibble {
+            write!(f, "{:x}", h)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Miner<'a> {
+    secret: &'a str,
+    target: Target,
+    start: u64,
+    end: Option<u64>,
+}
+
+impl<'a> Miner<'a> {
+    fn new(secret: &'a str, target: Target) -> Miner<'a> {
+        Miner {
+            secret,
+            target,
+            start: 0,
+            end: None,
+        }
+    }
+
+    /// First nonce to try
+    fn start(self, start: u64) -> Miner<'a> {
+        Miner { start, ..self }
+    }
+
+    /// Give up once this nonce is reached (exclusive)
+    fn end(self, end: u64) -> Miner<'a> {
+        Miner {
+            end: Some(end),
+            ..self
+        }
+    }
+
+    /// Hash state after consuming the secret, shared by every candidate
+    fn prefix(&self) -> md5::Context {
+        let mut ctx = md5::Context::new();
+        ctx.consume(self.secret.as_bytes());
+        ctx
+    }
+
+    /// Find the lowest nonce in range whose hash hits the target
+    fn mine(&self) -> Result<u64> {
+        if let Some(end) = self.end {
+            if end <= self.start {
+                anyhow::bail!(
+                    "the nonce range {}..{} is empty, end must be after start",
+                    self.start,
+                    end
+                );
+            }
+        }
+        let prefix = self.prefix();
+        let total = self.end.map(|end| end.saturating_sub(self.start));
+        let progress = Progress::new("day4 nonces", total);
+        let n = self.mine_with(&prefix, &progress);
+        progress.finish();
+        n
+    }
+
+    fn mine_with(&self, prefix: &md5::Context, progress: &Progress) -> Result<u64> {
+        if let Some(end) = self.end {
+            return scan(prefix, self.start, end, &self.target, progress)?.ok_or_else(|| {
+                ah!(
+                    "no nonce in {}..{} gives a hash starting with {:?}",
+                    self.start,
+                    end,
+                    self.target.to_string()
+                )
+            });
+        }
+
+        let mut chunk = self.start;
+        loop {
+            let end = chunk.saturating_add(CHUNK_SIZE);
+            log::debug!("scanning {}..{}", chunk, end);
+            if let Some(n) = scan(prefix, chunk, end, &self.target, progress)? {
+                return Ok(n);
+            }
+            if end == u64::MAX {
+                anyhow::bail!(
+                    "no nonce gives a hash starting with {:?}",
+                    self.target.to_string()
+                );
+            }
+            chunk = end;
+        }
+    }
+}
+
+/// Split `start..end` into blocks, searching blocks in parallel but in order
+///
+/// `find_map_first` only accepts a block's answer once every earlier block has
+/// come up empty, so the result is the lowest matching nonce.
+fn scan(
+    prefix: &md5::Context,
+    start: u64,
+    end: u64,
+    target: &Target,
+    progress: &Progress,
+) -> Result<Option<u64>> {
+    if start >= end {
+        return Ok(None);
+    }
+    let blocks = (end - start - 1) / BLOCK_SIZE + 1;
+    (0..blocks)
         .into_par_iter()
-        .find_first(|n| {
-            let d = hash(secret, *n);
-            f(d)
+        .find_map_first(|b| {
+            let lo = start + b * BLOCK_SIZE;
+            let hi = std::cmp::min(lo.saturating_add(BLOCK_SIZE), end);
+            let mut buf = NonceBuf::default();
+            let found = (lo..hi).find(|n| target.matches(hash(prefix, buf.format(*n))));
+            if found.is_none() {
+                if let Err(e) = progress.add(hi - lo) {
+                    return Some(Err(e));
+                }
+            }
+            found.map(Ok)
         })
-        .ok_or_else(|| anyhow::anyhow!("could not find value under {}", max))
+        .transpose()
 }
 
 #[inline]
-fn hash(secret: &str, n: u64) -> md5::Digest {
-    let mut ctx = md5::Context::new();
-    ctx.consume(secret.as_bytes());
-    let ascii_number = n.to_string();
-    ctx.consume(ascii_number.as_bytes());
+fn hash(prefix: &md5::Context, nonce: &[u8]) -> md5::Digest {
+    let mut ctx = prefix.clone();
+    ctx.consume(nonce);
     ctx.compute()
 }
 
-#[inline]
-fn is_five_zeros(digest: md5::Digest) -> bool {
-    let data: [u8; 16] = digest.into();
-    data[0] == 0 && data[1] == 0 && (data[2] & 0xF0) == 0
+/// Space for the decimal digits of any `u64`
+#[derive(Default)]
+struct NonceBuf {
+    buf: [u8; 20],
 }
 
-#[inline]
-fn is_six_zeros(digest: md5::Digest) -> bool {
-    let data: [u8; 16] = digest.into();
-    data[0] == 0 && data[1] == 0 && data[2] == 0
+impl NonceBuf {
+    #[inline]
+    fn format(&mut self, mut n: u64) -> &[u8] {
+        let mut idx = self.buf.len();
+        loop {
+            idx -= 1;
+            self.buf[idx] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        &self.buf[idx..]
+    }
 }
 
 #[cfg(test)]
@@ -64,6 +264,12 @@ mod tests {
         assert_eq!(part1("pqrstuv").unwrap().as_str(), "1048970")
     }
 
+    #[test]
+    fn surrounding_whitespace() {
+        // none of it is part of the secret
+        assert_eq!(part1("\n abcdef \r\n").unwrap().as_str(), "609043")
+    }
+
     #[test]
     fn verify_p1() {
         assert_eq!(part1(INPUT).unwrap().as_str(), "254575")
@@ -72,4 +278,130 @@ mod tests {
     fn verify_p2() {
         assert_eq!(part2(INPUT).unwrap().as_str(), "1038736")
     }
+
+    /// The original search: fresh context and a heap allocated nonce per candidate
+    fn naive_hash(secret: &str, n: u64) -> md5::Digest {
+        let mut ctx = md5::Context::new();
+        ctx.consume(secret.as_bytes());
+        let ascii_number = n.to_string();
+        ctx.consume(ascii_number.as_bytes());
+        ctx.compute()
+    }
+
+    fn naive_scan(secret: &str, start: u64, end: u64, target: &Target) -> Option<u64> {
+        (start..end)
+            .into_par_iter()
+            .find_first(|n| target.matches(naive_hash(secret, *n)))
+    }
+
+    #[test]
+    fn nonce_formatting() {
+        let mut buf = NonceBuf::default();
+        for n in [0, 7, 10, 609043, 1048970, u64::MAX] {
+            assert_eq!(buf.format(n), n.to_string().as_bytes());
+        }
+    }
+
+    #[test]
+    fn prefix_hash_matches_naive() {
+        let miner = Miner::new("abcdef", Target::leading_zeros(5).unwrap());
+        let prefix = miner.prefix();
+        let mut buf = NonceBuf::default();
+        for n in [0, 1, 609043, u64::MAX] {
+            assert_eq!(hash(&prefix, buf.format(n)), naive_hash("abcdef", n));
+        }
+    }
+
+    #[test]
+    fn scan_partial_blocks() {
+        let target = Target::leading_zeros(5).unwrap();
+        let prefix = Miner::new("abcdef", target.clone()).prefix();
+        let p = Progress::new("test", None);
+        assert_eq!(
+            scan(&prefix, 609043, 609044, &target, &p).unwrap(),
+            Some(609043)
+        );
+        assert_eq!(scan(&prefix, 609000, 609043, &target, &p).unwrap(), None);
+        assert_eq!(scan(&prefix, 10, 10, &target, &p).unwrap(), None);
+        assert_eq!(p.done(), 43);
+    }
+
+    /// Run with `cargo test --release bench_scan -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_scan() {
+        let secret = INPUT.trim();
+        let target = Target::leading_zeros(6).unwrap();
+        let prefix = Miner::new(secret, target.clone()).prefix();
+
+        let start = std::time::Instant::now();
+        let naive = naive_scan(secret, 0, 2000000, &target);
+        let naive_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let fast = scan(&prefix, 0, 2000000, &target, &Progress::new("bench", None)).unwrap();
+        let fast_time = start.elapsed();
+
+        assert_eq!(naive, fast);
+        println!(
+            "naive: {:?} prefix: {:?} ({:.2}x)",
+            naive_time,
+            fast_time,
+            naive_time.as_secs_f64() / fast_time.as_secs_f64()
+        );
+    }
+
+    #[test]
+    fn target_prefix() {
+        let t = Target::hex_prefix("000001dbb").unwrap();
+        assert_eq!(t.bytes, vec![0x00, 0x00, 0x01, 0xdb]);
+        assert_eq!(t.nibble, Some(0xb));
+        assert!(t.matches(naive_hash("abcdef", 609043)));
+        assert!(!Target::hex_prefix("000001dbc")
+            .unwrap()
+            .matches(naive_hash("abcdef", 609043)));
+        assert_eq!(t.to_string(), "000001dbb");
+        assert_eq!(Target::hex_prefix("00AB").unwrap().to_string(), "00ab");
+        assert_eq!(Target::leading_zeros(0).unwrap().to_string(), "");
+        assert!(Target::hex_prefix("00000g").is_err());
+        assert!(Target::leading_zeros(33).is_err());
+    }
+
+    #[test]
+    fn bounded_range() {
+        let miner = Miner::new("abcdef", Target::leading_zeros(5).unwrap());
+        assert_eq!(miner.start(600000).end(610000).mine().unwrap(), 609043);
+        let miner = Miner::new("abcdef", Target::leading_zeros(5).unwrap());
+        assert_eq!(
+            miner
+                .start(609044)
+                .end(610000)
+                .mine()
+                .unwrap_err()
+                .to_string(),
+            "no nonce in 609044..610000 gives a hash starting with \"00000\""
+        );
+        for (start, end) in &[(10, 5), (10, 10)] {
+            let miner = Miner::new("abcdef", Target::leading_zeros(5).unwrap());
+            assert_eq!(
+                miner
+                    .start(*start)
+                    .end(*end)
+                    .mine()
+                    .unwrap_err()
+                    .to_string(),
+                format!(
+                    "the nonce range {}..{} is empty, end must be after start",
+                    start, end
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn unbounded_crosses_chunks() {
+        // 1048970 is just past the first chunk
+        let miner = Miner::new("pqrstuv", Target::leading_zeros(5).unwrap());
+        assert_eq!(miner.mine().unwrap(), 1048970);
+    }
 }
@@ -1,102 +1,89 @@
-use std::collections::HashMap;
+use std::ops::Range;
 
 use anyhow::Result;
 
+use super::Options;
+
+mod rules;
+
+use rules::RuleSet;
+
 pub fn part1(input: &str) -> Result<String> {
-    let x = input.lines().filter(|l| check_str(l)).count();
+    let rules = RuleSet::part1();
+    let x = input.lines().filter(|l| rules.is_nice(l)).count();
     Ok(format!("{:?}", x))
 }
 
 pub fn part2(input: &str) -> Result<String> {
-    let x = input.lines().filter(|l| check_str_2(l)).count();
+    let rules = RuleSet::part2();
+    let x = input.lines().filter(|l| rules.is_nice(l)).count();
     Ok(format!("{:?}", x))
 }
 
-#[inline]
-fn is_vowel(c: char) -> bool {
-    match c {
-        'a' => true,
-        'e' => true,
-        'i' => true,
-        'o' => true,
-        'u' => true,
-        _ => false,
-    }
-}
-
-#[inline]
-fn is_double(c1: char, c2: char) -> bool {
-    c1 == c2
-}
-
-#[inline]
-fn is_banned(c1: char, c2: char) -> bool {
-    match (c1, c2) {
-        ('a', 'b') => true,
-        ('c', 'd') => true,
-        ('p', 'q') => true,
-        ('x', 'y') => true,
-        _ => false,
-    }
-}
-
-fn check_str(s: &str) -> bool {
-    let mut vowels = 0;
-    let mut double = false;
-    let mut prev = None;
-    for c in s.chars() {
-        if is_vowel(c) {
-            vowels += 1;
-        }
-        if let Some(c1) = prev {
-            if is_banned(c1, c) {
-                log::trace!("{:?} ban: {}{}", s, c1, c);
-                return false;
-            }
-            double = double || is_double(c1, c);
+/// Count nice strings under any rule set, naming the rules each naughty one broke
+///
+/// Options: `rules=part1|part2|RULE,...` (default `part1`), see [`RuleSet`]
+pub fn check(input: &str, opts: &Options) -> Result<String> {
+    let rules = opts.get("rules")?.unwrap_or_else(RuleSet::part1);
+    let mut nice = 0;
+    let names = rules.rules().iter().map(|r| r.name()).collect::<Vec<_>>();
+    let mut lines = vec![format!("rules: {}", names.join("; "))];
+    for line in input.lines() {
+        let failed = rules.failures(line);
+        if failed.is_empty() {
+            nice += 1;
+            continue;
         }
-        prev = Some(c);
+        let names = failed.iter().map(|r| r.name()).collect::<Vec<_>>();
+        lines.push(format!("{}: naughty, needs {}", line, names.join("; ")));
     }
-    log::trace!("{:?} double: {:?} vowels: {}", s, double, vowels);
-    double && vowels >= 3
-}
-
-#[derive(Default, Debug)]
-struct PairKeeper {
-    inner: HashMap<(char, char), usize>,
+    lines.push(format!("nice: {}", nice));
+    Ok(lines.join("\n"))
 }
 
-impl PairKeeper {
-    fn check(&mut self, pos: usize, c1: char, c2: char) -> bool {
-        let key = (c1, c2);
-        match self.inner.entry(key) {
-            std::collections::hash_map::Entry::Occupied(o) => o.get() + 1 < pos,
-            std::collections::hash_map::Entry::Vacant(v) => {
-                v.insert(pos);
-                false
+/// For every line, each rule's verdict with the letters that decided it in
+/// upper case
+///
+/// Options: `rules` as for [`check`]
+pub fn explain(input: &str, opts: &Options) -> Result<String> {
+    let rules = opts.get("rules")?.unwrap_or_else(RuleSet::part1);
+    let mut lines = Vec::new();
+    for line in input.lines() {
+        let verdicts = rules.judge(line);
+        let nice = verdicts.iter().all(|(_, v)| v.passed);
+        lines.push(format!(
+            "{}: {}",
+            line,
+            if nice { "nice" } else { "naughty" }
+        ));
+        for (rule, verdict) in verdicts {
+            let mut entry = format!(
+                "  {} {}",
+                if verdict.passed { "pass" } else { "FAIL" },
+                rule.name()
+            );
+            if !verdict.spans.is_empty() {
+                entry.push_str(": ");
+                entry.push_str(&highlight(line, &verdict.spans));
             }
+            lines.push(entry);
         }
     }
+    Ok(lines.join("\n"))
 }
 
-fn check_str_2(s: &str) -> bool {
-    let mut n1 = None;
-    let mut n2 = None;
-    let mut pair = false;
-    let mut skip = false;
-    let mut pk = PairKeeper::default();
-    for (pos, c) in s.chars().enumerate() {
-        if let Some(c1) = n1 {
-            pair = pair || pk.check(pos, c, c1);
-            skip = skip || if let Some(c2) = n2 { c2 == c } else { false }
-        }
-        if pair && skip {
-            return true;
-        }
-        n2 = n1.take();
-        n1 = Some(c);
-    }
-    false
+/// `s` with the characters inside any of `spans` in upper case
+fn highlight(s: &str, spans: &[Range<usize>]) -> String {
+    s.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if spans.iter().any(|r| r.contains(&i)) {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -104,22 +91,6 @@ mod tests {
     use super::*;
     const INPUT: &str = include_str!("../../../input/day5");
 
-    #[test]
-    fn pair_exact() {
-        let mut pk = PairKeeper::default();
-        assert_eq!(pk.check(0, 'a', 'b'), false);
-        assert_eq!(pk.check(1, 'b', 'a'), false);
-        assert_eq!(pk.check(2, 'a', 'b'), true);
-    }
-
-    #[test]
-    fn pair_triple() {
-        let mut pk = PairKeeper::default();
-        assert_eq!(pk.check(0, 'a', 'a'), false);
-        assert_eq!(pk.check(1, 'a', 'a'), false);
-        assert_eq!(pk.check(2, 'a', 'a'), true);
-    }
-
     #[test]
     fn ex1() {
         assert_eq!(part1("ugknbfddgicrmopn").unwrap().as_str(), "1")
@@ -158,6 +129,54 @@ mod tests {
         assert_eq!(part2("ieodomkazucvgmuy").unwrap().as_str(), "0")
     }
 
+    #[test]
+    fn check_report() {
+        let input = "ugknbfddgicrmopn\ndvszwmarrgswjxmb\njchzalrnumimnmhp\nqjhvhtzxzqqjkmpb";
+        assert_eq!(
+            check(input, &Options::default()).unwrap(),
+            "rules: at least 3 vowels; a double letter; none of ab, cd, pq, xy\n\
+             dvszwmarrgswjxmb: naughty, needs at least 3 vowels\n\
+             jchzalrnumimnmhp: naughty, needs a double letter\n\
+             qjhvhtzxzqqjkmpb: naughty, needs at least 3 vowels\n\
+             nice: 1"
+        );
+        let opts = Options::from_pairs(&[("rules", "part2")]);
+        assert!(check(input, &opts).unwrap().ends_with("nice: 1"));
+        let opts = Options::from_pairs(&[("rules", "vowels:1")]);
+        assert_eq!(
+            check(input, &opts).unwrap(),
+            "rules: at least 1 vowels\n\
+             qjhvhtzxzqqjkmpb: naughty, needs at least 1 vowels\n\
+             nice: 3"
+        );
+    }
+
+    #[test]
+    fn explain_report() {
+        let input = "haegwjzuvuyypxyu\nqjhvhtzxzqqjkmpb";
+        assert_eq!(
+            explain(input, &Options::default()).unwrap(),
+            "haegwjzuvuyypxyu: naughty\n\
+             \x20 pass at least 3 vowels: hAEgwjzUvUyypxyU\n\
+             \x20 pass a double letter: haegwjzuvuYYpxyu\n\
+             \x20 FAIL none of ab, cd, pq, xy: haegwjzuvuyypXYu\n\
+             qjhvhtzxzqqjkmpb: naughty\n\
+             \x20 FAIL at least 3 vowels\n\
+             \x20 pass a double letter: qjhvhtzxzQQjkmpb\n\
+             \x20 pass none of ab, cd, pq, xy"
+        );
+        let opts = Options::from_pairs(&[("rules", "part2")]);
+        assert_eq!(
+            explain("qjhvhtzxzqqjkmpb\naaa", &opts).unwrap(),
+            "qjhvhtzxzqqjkmpb: nice\n\
+             \x20 pass a pair twice without overlapping: QJhvhtzxzqQJkmpb\n\
+             \x20 pass a letter either side of another: qjHVHtzxzqqjkmpb\n\
+             aaa: naughty\n\
+             \x20 FAIL a pair twice without overlapping\n\
+             \x20 pass a letter either side of another: AAA"
+        );
+    }
+
     #[test]
     fn verify_p1() {
         assert_eq!(part1(INPUT).unwrap().as_str(), "258")
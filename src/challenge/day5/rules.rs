@@ -0,0 +1,368 @@
+//! Rules a string has to follow to be nice
+//!
+//! Each rule is its own type with a name, and a [`RuleSet`] is nice only when
+//! every rule passes. Sets can be written out as a list such as
+//! `vowels:3,repeat:0,banned:ab/cd/pq/xy`, or picked by the name of a part.
+
+use std::{collections::HashMap, fmt, ops::Range, str::FromStr};
+
+use anyhow::{Context, Result};
+
+/// Whether a string passed a rule, and the characters that decided it
+#[derive(Debug, PartialEq)]
+pub struct Verdict {
+    pub passed: bool,
+    pub spans: Vec<Range<usize>>,
+}
+
+/// One test a string has to pass
+pub trait Rule: fmt::Debug {
+    fn name(&self) -> String;
+    fn judge(&self, s: &[char]) -> Verdict;
+
+    fn check(&self, s: &[char]) -> bool {
+        self.judge(s).passed
+    }
+}
+
+/// At least `min` of the letters in `vowels`
+#[derive(Debug)]
+pub struct Vowels {
+    pub min: usize,
+    pub vowels: Vec<char>,
+}
+
+impl Rule for Vowels {
+    fn name(&self) -> String {
+        format!("at least {} vowels", self.min)
+    }
+    /// Every vowel found, even when there are too few
+    fn judge(&self, s: &[char]) -> Verdict {
+        let spans = (0..s.len())
+            .filter(|&i| self.vowels.contains(&s[i]))
+            .map(|i| i..i + 1)
+            .collect::<Vec<_>>();
+        Verdict {
+            passed: spans.len() >= self.min,
+            spans,
+        }
+    }
+}
+
+/// A letter that appears again after exactly `gap` other letters
+///
+/// A gap of 0 is a doubled letter like `aa`, and 1 is a sandwich like `xyx`.
+#[derive(Debug)]
+pub struct Repeat {
+    pub gap: usize,
+}
+
+impl Rule for Repeat {
+    fn name(&self) -> String {
+        match self.gap {
+            0 => "a double letter".to_string(),
+            1 => "a letter either side of another".to_string(),
+            n => format!("a letter repeated {} apart", n + 1),
+        }
+    }
+    /// The first repeat, from one letter to the other
+    fn judge(&self, s: &[char]) -> Verdict {
+        let len = self.gap + 2;
+        let found = s.windows(len).position(|w| w[0] == w[len - 1]);
+        Verdict {
+            passed: found.is_some(),
+            spans: found.map(|i| i..i + len).into_iter().collect(),
+        }
+    }
+}
+
+/// None of these letter pairs
+#[derive(Debug)]
+pub struct Banned {
+    pub pairs: Vec<(char, char)>,
+}
+
+impl Rule for Banned {
+    fn name(&self) -> String {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(a, b)| format!("{}{}", a, b))
+            .collect::<Vec<_>>();
+        format!("none of {}", pairs.join(", "))
+    }
+    /// Every banned pair found
+    fn judge(&self, s: &[char]) -> Verdict {
+        let spans = s
+            .windows(2)
+            .enumerate()
+            .filter(|(_, w)| self.pairs.contains(&(w[0], w[1])))
+            .map(|(i, _)| i..i + 2)
+            .collect::<Vec<_>>();
+        Verdict {
+            passed: spans.is_empty(),
+            spans,
+        }
+    }
+}
+
+/// A pair of letters that appears twice without overlapping
+#[derive(Debug)]
+pub struct RepeatedPair;
+
+impl Rule for RepeatedPair {
+    fn name(&self) -> String {
+        "a pair twice without overlapping".to_string()
+    }
+    /// Both copies of the first pair seen again
+    fn judge(&self, s: &[char]) -> Verdict {
+        let mut pk = PairKeeper::default();
+        let found = s
+            .windows(2)
+            .enumerate()
+            .find_map(|(i, w)| Some((pk.earlier(i + 1, w[1], w[0])? - 1, i)));
+        Verdict {
+            passed: found.is_some(),
+            spans: found
+                .map(|(first, second)| vec![first..first + 2, second..second + 2])
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct PairKeeper {
+    inner: HashMap<(char, char), usize>,
+}
+
+impl PairKeeper {
+    /// Where this pair was first seen, if that copy doesn't overlap the one at `pos`
+    pub fn earlier(&mut self, pos: usize, c1: char, c2: char) -> Option<usize> {
+        let key = (c1, c2);
+        match self.inner.entry(key) {
+            std::collections::hash_map::Entry::Occupied(o) => {
+                Some(*o.get()).filter(|first| first + 1 < pos)
+            }
+            std::collections::hash_map::Entry::Vacant(v) => {
+                v.insert(pos);
+                None
+            }
+        }
+    }
+}
+
+/// Every rule a nice string has to pass
+#[derive(Debug)]
+pub struct RuleSet {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl RuleSet {
+    /// Three vowels, a double letter and none of `ab`, `cd`, `pq` or `xy`
+    pub fn part1() -> RuleSet {
+        "vowels:3,repeat:0,banned:ab/cd/pq/xy"
+            .parse()
+            .expect("part 1 rules are valid")
+    }
+
+    /// A repeated pair and a letter either side of another
+    pub fn part2() -> RuleSet {
+        "pair,repeat:1".parse().expect("part 2 rules are valid")
+    }
+
+    pub fn rules(&self) -> &[Box<dyn Rule>] {
+        &self.rules
+    }
+
+    pub fn is_nice(&self, s: &str) -> bool {
+        let chars = s.chars().collect::<Vec<_>>();
+        self.rules.iter().all(|r| r.check(&chars))
+    }
+
+    /// Every rule with its verdict on `s`, in order
+    pub fn judge<'a>(&'a self, s: &str) -> Vec<(&'a dyn Rule, Verdict)> {
+        let chars = s.chars().collect::<Vec<_>>();
+        self.rules
+            .iter()
+            .map(|r| (r.as_ref(), r.judge(&chars)))
+            .collect()
+    }
+
+    /// The rules `s` does not pass
+    pub fn failures(&self, s: &str) -> Vec<&dyn Rule> {
+        let chars = s.chars().collect::<Vec<_>>();
+        self.rules
+            .iter()
+            .filter(|r| !r.check(&chars))
+            .map(|r| r.as_ref())
+            .collect()
+    }
+}
+
+/// `part1`, `part2`, or a comma separated list of `vowels[:N]`,
+/// `repeat[:GAP]`, `banned:XY/XY/...` and `pair`
+impl FromStr for RuleSet {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "part1" => return Ok(RuleSet::part1()),
+            "part2" => return Ok(RuleSet::part2()),
+            _ => {}
+        }
+        let rules = s
+            .split(',')
+            .map(|spec| parse_rule(spec.trim()).with_context(|| format!("rule {:?}", spec)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RuleSet { rules })
+    }
+}
+
+fn parse_rule(spec: &str) -> Result<Box<dyn Rule>> {
+    let (name, arg) = match spec.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (spec, None),
+    };
+    let number = |default: usize| -> Result<usize> {
+        match arg {
+            Some(a) => a
+                .parse()
+                .with_context(|| format!("{:?} is not a number", a)),
+            None => Ok(default),
+        }
+    };
+    Ok(match name {
+        "vowels" => Box::new(Vowels {
+            min: number(3)?,
+            vowels: "aeiou".chars().collect(),
+        }),
+        "repeat" => Box::new(Repeat { gap: number(0)? }),
+        "pair" => Box::new(RepeatedPair),
+        "banned" => {
+            let pairs = arg
+                .context("banned needs a list of pairs like `ab/cd`")?
+                .split('/')
+                .map(|p| match p.chars().collect::<Vec<_>>()[..] {
+                    [a, b] => Ok((a, b)),
+                    _ => anyhow::bail!("{:?} is not a pair of letters", p),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Box::new(Banned { pairs })
+        }
+        _ => anyhow::bail!("unknown rule, expected `vowels`, `repeat`, `banned` or `pair`"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn pair_exact() {
+        let mut pk = PairKeeper::default();
+        assert_eq!(pk.earlier(0, 'a', 'b'), None);
+        assert_eq!(pk.earlier(1, 'b', 'a'), None);
+        assert_eq!(pk.earlier(2, 'a', 'b'), Some(0));
+    }
+
+    #[test]
+    fn pair_triple() {
+        let mut pk = PairKeeper::default();
+        assert_eq!(pk.earlier(0, 'a', 'a'), None);
+        assert_eq!(pk.earlier(1, 'a', 'a'), None);
+        assert_eq!(pk.earlier(2, 'a', 'a'), Some(0));
+    }
+
+    #[test]
+    fn single_rules() {
+        assert!(RepeatedPair.check(&chars("xyxy")));
+        assert!(RepeatedPair.check(&chars("aabcdefgaa")));
+        assert!(!RepeatedPair.check(&chars("aaa")));
+        assert!(Repeat { gap: 1 }.check(&chars("abcdefeghi")));
+        assert!(!Repeat { gap: 1 }.check(&chars("abcd")));
+        assert!(Repeat { gap: 2 }.check(&chars("abca")));
+        assert!(!Repeat { gap: 0 }.check(&chars("a")));
+    }
+
+    #[test]
+    fn verdict_spans() {
+        let judge = |rule: &dyn Rule, s| rule.judge(&chars(s));
+        assert_eq!(
+            judge(&RepeatedPair, "qjhvhtzxzqqjkmpb"),
+            Verdict {
+                passed: true,
+                spans: vec![0..2, 10..12]
+            }
+        );
+        assert!(judge(&RepeatedPair, "aaa").spans.is_empty());
+        assert_eq!(judge(&RepeatedPair, "aaaa").spans, vec![0..2, 2..4]);
+        assert_eq!(judge(&Repeat { gap: 1 }, "qjhvhtzx").spans, vec![2..5]);
+        assert_eq!(judge(&Repeat { gap: 0 }, "abccdd").spans, vec![2..4]);
+
+        let banned = judge(
+            &Banned {
+                pairs: vec![('x', 'y')],
+            },
+            "axyzxy",
+        );
+        assert!(!banned.passed);
+        assert_eq!(banned.spans, vec![1..3, 4..6]);
+
+        let vowels = Vowels {
+            min: 3,
+            vowels: "aeiou".chars().collect(),
+        };
+        assert_eq!(
+            judge(&vowels, "bead"),
+            Verdict {
+                passed: false,
+                spans: vec![1..2, 2..3]
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rule_sets() {
+        let set = "vowels:2, repeat, banned:ab/xy".parse::<RuleSet>().unwrap();
+        let names = set.rules().iter().map(|r| r.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["at least 2 vowels", "a double letter", "none of ab, xy"]
+        );
+        assert!(set.is_nice("aeebc"));
+        assert!(!set.is_nice("aeebxy"));
+
+        let err = |s: &str| format!("{:#}", s.parse::<RuleSet>().unwrap_err());
+        assert_eq!(
+            err("vowels,shiny"),
+            "rule \"shiny\": unknown rule, expected `vowels`, `repeat`, `banned` or `pair`"
+        );
+        assert_eq!(
+            err("vowels:x"),
+            "rule \"vowels:x\": \"x\" is not a number: invalid digit found in string"
+        );
+        assert_eq!(
+            err("banned:abc"),
+            "rule \"banned:abc\": \"abc\" is not a pair of letters"
+        );
+        assert!("banned".parse::<RuleSet>().is_err());
+    }
+
+    #[test]
+    fn failures() {
+        let names = |s| {
+            RuleSet::part1()
+                .failures(s)
+                .iter()
+                .map(|r| r.name())
+                .collect::<Vec<_>>()
+        };
+        assert!(names("ugknbfddgicrmopn").is_empty());
+        assert_eq!(names("jchzalrnumimnmhp"), vec!["a double letter"]);
+        assert_eq!(names("haegwjzuvuyypxyu"), vec!["none of ab, cd, pq, xy"]);
+        assert_eq!(names("dvszwmarrgswjxmb"), vec!["at least 3 vowels"]);
+    }
+}
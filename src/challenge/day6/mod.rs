@@ -1,31 +1,25 @@
 use anyhow::Result;
 use aoc::Point;
+use rayon::prelude::*;
 
 const GRID_SIZE: usize = 1000;
 
+#[cfg(test)]
 type Grid<T> = aoc::grid::fixed_grid::FixedGrid<T>;
 
 pub fn part1(input: &str) -> Result<String> {
-    let mut grid = Grid::<Bulb>::from_dimm(GRID_SIZE, GRID_SIZE);
-    for instr in input.lines().map(parse_line) {
-        let instr = instr?;
-        grid_apply(instr.p1, instr.p2, &mut grid, |b: &mut Bulb| {
-            instr.operation.op(b)
-        });
-    }
-    let x = grid.raw_iter().filter(|b| **b == Bulb::On).count();
+    let instructions = parse_input(input)?;
+    let grid = par_grid_apply(&instructions, |op: &Operation, b: &mut Bulb| op.op(b));
+    let x = grid.iter().filter(|b| **b == Bulb::On).count();
     Ok(format!("{:?}", x))
 }
 
 pub fn part2(input: &str) -> Result<String> {
-    let mut grid = Grid::<ValueBulb>::from_dimm(GRID_SIZE, GRID_SIZE);
-    for instr in input.lines().map(parse_line) {
-        let instr = instr?;
-        grid_apply(instr.p1, instr.p2, &mut grid, |b: &mut ValueBulb| {
-            instr.operation.value(b)
-        });
-    }
-    let x: i64 = grid.raw_iter().map(|b| b.0).sum();
+    let instructions = parse_input(input)?;
+    let grid = par_grid_apply(&instructions, |op: &Operation, b: &mut ValueBulb| {
+        op.value(b)
+    });
+    let x: i64 = grid.iter().map(|b| b.0).sum();
     Ok(format!("{:?}", x))
 }
 
@@ -59,6 +53,10 @@ struct Instruction {
     p2: Point<i64>,
 }
 
+fn parse_input(input: &str) -> Result<Vec<Instruction>> {
+    input.lines().map(parse_line).collect()
+}
+
 fn parse_line(s: &str) -> Result<Instruction> {
     let mut operation = None;
     let mut point = vec![];
@@ -135,16 +133,21 @@ impl Default for Bulb {
     }
 }
 
+/// Inclusive `(min, max)` of two coordinates
+#[inline]
+fn span(a: i64, b: i64) -> (i64, i64) {
+    (std::cmp::min(a, b), std::cmp::max(a, b))
+}
+
+#[cfg(test)]
 fn grid_apply<T, F>(p1: Point<i64>, p2: Point<i64>, grid: &mut Grid<T>, f: F)
 where
     F: Fn(&mut T),
 {
-    let x1 = std::cmp::min(p1.x, p2.x);
-    let x2 = std::cmp::max(p1.x, p2.x);
+    let (x1, x2) = span(p1.x, p2.x);
     let stride = (x2 - x1) as usize + 1;
 
-    let y1 = std::cmp::min(p1.y, p2.y);
-    let y2 = std::cmp::max(p1.y, p2.y);
+    let (y1, y2) = span(p1.y, p2.y);
 
     for y in y1..y2 + 1 {
         let start = Point::new(x1, y);
@@ -155,6 +158,54 @@ where
     }
 }
 
+/// Apply every instruction in order, one rectangle at a time
+///
+/// Reference implementation for checking `par_grid_apply`
+#[cfg(test)]
+fn serial_grid_apply<T, F>(instructions: &[Instruction], f: F) -> Grid<T>
+where
+    T: Default + Clone,
+    F: Fn(&Operation, &mut T),
+{
+    let mut grid = Grid::<T>::from_dimm(GRID_SIZE, GRID_SIZE);
+    for instr in instructions {
+        grid_apply(instr.p1, instr.p2, &mut grid, |b: &mut T| {
+            f(&instr.operation, b)
+        });
+    }
+    grid
+}
+
+/// Apply every instruction in order, with each row of the grid handled in parallel
+///
+/// Rows never affect each other, so each row can replay the full instruction
+/// list independently and still end up in the same state as the serial version.
+/// The result is the grid in row-major order.
+fn par_grid_apply<T, F>(instructions: &[Instruction], f: F) -> Vec<T>
+where
+    T: Default + Clone + Send,
+    F: Fn(&Operation, &mut T) + Sync,
+{
+    let mut cells = vec![T::default(); GRID_SIZE * GRID_SIZE];
+    cells
+        .par_chunks_mut(GRID_SIZE)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let y = y as i64;
+            for instr in instructions {
+                let (y1, y2) = span(instr.p1.y, instr.p2.y);
+                if y < y1 || y > y2 {
+                    continue;
+                }
+                let (x1, x2) = span(instr.p1.x, instr.p2.x);
+                for b in &mut row[x1 as usize..x2 as usize + 1] {
+                    f(&instr.operation, b)
+                }
+            }
+        });
+    cells
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,4 +219,52 @@ mod tests {
     fn verify_p2() {
         assert_eq!(part2(INPUT).unwrap().as_str(), "15343601")
     }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let instructions = parse_input(INPUT).unwrap();
+
+        let serial = serial_grid_apply(&instructions, |op: &Operation, b: &mut Bulb| op.op(b));
+        let parallel = par_grid_apply(&instructions, |op: &Operation, b: &mut Bulb| op.op(b));
+        assert!(serial.raw_iter().eq(parallel.iter()));
+
+        let serial = serial_grid_apply(&instructions, |op: &Operation, b: &mut ValueBulb| {
+            op.value(b)
+        });
+        let parallel = par_grid_apply(&instructions, |op: &Operation, b: &mut ValueBulb| {
+            op.value(b)
+        });
+        assert!(serial.raw_iter().eq(parallel.iter()));
+    }
+
+    /// Run with `cargo test --release bench_grid_apply -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_grid_apply() {
+        const ROUNDS: u32 = 10;
+        let instructions = parse_input(INPUT).unwrap();
+
+        let start = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            serial_grid_apply(&instructions, |op: &Operation, b: &mut ValueBulb| {
+                op.value(b)
+            });
+        }
+        let serial = start.elapsed() / ROUNDS;
+
+        let start = std::time::Instant::now();
+        for _ in 0..ROUNDS {
+            par_grid_apply(&instructions, |op: &Operation, b: &mut ValueBulb| {
+                op.value(b)
+            });
+        }
+        let parallel = start.elapsed() / ROUNDS;
+
+        println!(
+            "serial: {:?} parallel: {:?} ({:.2}x)",
+            serial,
+            parallel,
+            serial.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
 }
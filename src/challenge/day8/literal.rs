@@ -0,0 +1,159 @@
+//! Santa's string literals: double quoted, with `\\`, `\"` and `\xHH` escapes
+//!
+//! A decoded string holds one `char` per byte in memory, so `\xHH` becomes the
+//! character with code point `HH`.
+
+use std::{convert::TryFrom, str::FromStr};
+
+use anyhow::{anyhow as ah, Result};
+
+/// The string a literal stands for
+pub fn decode(literal: &str) -> Result<String> {
+    let mut chars = literal.chars().zip(1..);
+    match chars.next() {
+        Some(('"', _)) => {}
+        found => anyhow::bail!(
+            "expected an opening quote at column 1, found {:?}",
+            found.map(|(c, _)| c)
+        ),
+    }
+    let mut decoded = String::new();
+    let end = literal.chars().count() + 1;
+    loop {
+        let (c, col) = chars
+            .next()
+            .ok_or_else(|| ah!("missing closing quote at column {}", end))?;
+        match c {
+            '"' => break,
+            '\\' => {
+                let (escape, _) = chars
+                    .next()
+                    .ok_or_else(|| ah!("missing closing quote at column {}", end))?;
+                match escape {
+                    '\\' | '"' => decoded.push(escape),
+                    'x' => {
+                        let digits = chars.by_ref().take(2).map(|(c, _)| c).collect::<String>();
+                        let byte = Some(&digits)
+                            .filter(|d| d.len() == 2)
+                            .and_then(|d| u8::from_str_radix(d, 16).ok())
+                            .ok_or_else(|| {
+                                ah!(
+                                    "escape \\x{} at column {} needs two hex digits",
+                                    digits,
+                                    col
+                                )
+                            })?;
+                        decoded.push(char::from(byte));
+                    }
+                    _ => anyhow::bail!("unknown escape \\{} at column {}", escape, col),
+                }
+            }
+            _ => decoded.push(c),
+        }
+    }
+    if let Some((_, col)) = chars.next() {
+        let rest = literal.chars().skip(col - 1).collect::<String>();
+        anyhow::bail!("unexpected trailing text at column {}: {:?}", col, rest);
+    }
+    Ok(decoded)
+}
+
+/// Which characters [`encode`] writes as escapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escaping {
+    /// Only quotes and backslashes, as the puzzle does
+    Puzzle,
+    /// Also anything outside printable ASCII as `\xHH`, which has to fit in a byte
+    Bytes,
+}
+
+impl FromStr for Escaping {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "puzzle" => Ok(Escaping::Puzzle),
+            "bytes" => Ok(Escaping::Bytes),
+            _ => anyhow::bail!("expected `puzzle` or `bytes`"),
+        }
+    }
+}
+
+/// A literal that decodes back to `s`
+pub fn encode(s: &str, escaping: Escaping) -> Result<String> {
+    let mut encoded = String::from('"');
+    for (c, col) in s.chars().zip(1..) {
+        match c {
+            '"' | '\\' => {
+                encoded.push('\\');
+                encoded.push(c);
+            }
+            ' '..='~' => encoded.push(c),
+            _ if escaping == Escaping::Puzzle => encoded.push(c),
+            _ => {
+                let byte = u8::try_from(c)
+                    .map_err(|_| ah!("{:?} at column {} does not fit in a byte", c, col))?;
+                encoded.push_str(&format!("\\x{:02x}", byte));
+            }
+        }
+    }
+    encoded.push('"');
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_escapes() {
+        assert_eq!(decode(r#""""#).unwrap(), "");
+        assert_eq!(decode(r#""a\\b\"c""#).unwrap(), "a\\b\"c");
+        assert_eq!(decode(r#""\x27\x41""#).unwrap(), "'A");
+        assert_eq!(decode(r#""\xff""#).unwrap(), "\u{ff}");
+    }
+
+    #[test]
+    fn decode_errors() {
+        let err = |s| decode(s).unwrap_err().to_string();
+        assert_eq!(
+            err("abc"),
+            "expected an opening quote at column 1, found Some('a')"
+        );
+        assert_eq!(err(""), "expected an opening quote at column 1, found None");
+        assert_eq!(err(r#""abc"#), "missing closing quote at column 5");
+        assert_eq!(err(r#""abc\"#), "missing closing quote at column 6");
+        assert_eq!(err(r#""a\qb""#), "unknown escape \\q at column 3");
+        assert_eq!(
+            err(r#""a\x4g""#),
+            "escape \\x4g at column 3 needs two hex digits"
+        );
+        assert_eq!(
+            err(r#""\x4"#),
+            "escape \\x4 at column 2 needs two hex digits"
+        );
+        assert_eq!(
+            err(r#""ab"cd"#),
+            "unexpected trailing text at column 5: \"cd\""
+        );
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        for s in ["", "abc", "aaa\"aaa", "\\x27", "tab\there\u{ff}"] {
+            assert_eq!(decode(&encode(s, Escaping::Bytes).unwrap()).unwrap(), s);
+            assert_eq!(decode(&encode(s, Escaping::Puzzle).unwrap()).unwrap(), s);
+        }
+        assert_eq!(encode("a\tb", Escaping::Puzzle).unwrap(), "\"a\tb\"");
+        assert_eq!(encode("a\tb", Escaping::Bytes).unwrap(), r#""a\x09b""#);
+        assert_eq!(
+            encode("snow\u{2603}", Escaping::Puzzle).unwrap(),
+            "\"snow\u{2603}\""
+        );
+        assert_eq!(
+            encode("snow\u{2603}", Escaping::Bytes)
+                .unwrap_err()
+                .to_string(),
+            "'☃' at column 5 does not fit in a byte"
+        );
+    }
+}
@@ -1,67 +1,35 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-pub fn part1(input: &str) -> Result<String> {
-    let x = input.lines().map(delta).sum::<usize>();
-    Ok(format!("{:?}", x))
-}
+use super::Options;
 
-pub fn part2(input: &str) -> Result<String> {
-    let x = input.lines().map(delta_encode).sum::<usize>();
-    Ok(format!("{:?}", x))
-}
+mod literal;
 
-fn delta(s: &str) -> usize {
-    s.len() - count_quoted(s)
-}
+use literal::{decode, encode, Escaping};
 
-fn delta_encode(s: &str) -> usize {
-    escape_len(s) - s.len()
+pub fn part1(input: &str) -> Result<String> {
+    let x = for_each_line(input, |line| {
+        Ok(line.chars().count() - decode(line)?.chars().count())
+    })?;
+    Ok(format!("{:?}", x))
 }
 
-fn escape_len(s: &str) -> usize {
-    s.chars()
-        .map(|c| match c {
-            '"' => 2,
-            '\\' => 2,
-            _ => 1,
-        })
-        .sum::<usize>()
-        + 2
+/// Options: `escape=puzzle|bytes` (default `puzzle`), where `bytes` also
+/// escapes anything outside printable ASCII
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    let escaping = opts.get("escape")?.unwrap_or(Escaping::Puzzle);
+    let x = for_each_line(input, |line| {
+        Ok(encode(line, escaping)?.chars().count() - line.chars().count())
+    })?;
+    Ok(format!("{:?}", x))
 }
 
-fn count_quoted(s: &str) -> usize {
-    let mut escape = false;
-    let mut hex = None;
-    let mut count = 0;
-    for (idx, c) in s.chars().enumerate() {
-        if idx == 0 {
-            assert_eq!(c, '"');
-            continue;
-        }
-        let last_escape = escape;
-        escape = false;
-        if let Some(skip_more) = hex {
-            if skip_more {
-                hex = Some(false);
-            } else {
-                hex = None;
-            }
-            continue;
-        }
-        match (last_escape, c) {
-            (true, 'x') => {
-                count += 1;
-                hex = Some(true)
-            }
-            (true, '\\') => count += 1,
-            (true, '"') => count += 1,
-            (false, '"') => return count,
-            (false, '\\') => escape = true,
-            (true, _) => panic!("unknown escape char: {:?} in {:?}", c, s),
-            (false, _) => count += 1,
-        }
-    }
-    panic!("string did not end in quotation: {:?}", s)
+/// Sum `f` over every line, saying which line it failed on
+fn for_each_line(input: &str, f: impl Fn(&str) -> Result<usize>) -> Result<usize> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| f(line).with_context(|| format!("line {}", idx + 1)))
+        .sum()
 }
 
 #[cfg(test)]
@@ -71,36 +39,79 @@ mod tests {
 
     #[test]
     fn ex_1() {
-        assert_eq!(count_quoted(r##""""##), 0);
+        assert_eq!(decode(r##""""##).unwrap().chars().count(), 0);
     }
     #[test]
     fn ex_2() {
-        assert_eq!(count_quoted(r##""abc""##), 3);
+        assert_eq!(decode(r##""abc""##).unwrap().chars().count(), 3);
     }
     #[test]
     fn ex_3() {
-        assert_eq!(count_quoted(r##""aaa\"aaa""##), 7);
+        assert_eq!(decode(r##""aaa\"aaa""##).unwrap().chars().count(), 7);
     }
     #[test]
     fn ex_4() {
-        assert_eq!(count_quoted(r##""\x27""##), 1);
+        assert_eq!(decode(r##""\x27""##).unwrap().chars().count(), 1);
     }
 
     #[test]
     fn pt2_ex_1() {
-        assert_eq!(escape_len(r##""""##), 6);
+        assert_eq!(
+            encode(r##""""##, Escaping::Puzzle).unwrap().chars().count(),
+            6
+        );
     }
     #[test]
     fn pt2_ex_2() {
-        assert_eq!(escape_len(r##""abc""##), 9);
+        assert_eq!(
+            encode(r##""abc""##, Escaping::Puzzle)
+                .unwrap()
+                .chars()
+                .count(),
+            9
+        );
     }
     #[test]
     fn pt2_ex_3() {
-        assert_eq!(escape_len(r##""aaa\"aaa""##), 16);
+        assert_eq!(
+            encode(r##""aaa\"aaa""##, Escaping::Puzzle)
+                .unwrap()
+                .chars()
+                .count(),
+            16
+        );
     }
     #[test]
     fn pt2_ex_4() {
-        assert_eq!(escape_len(r##""\x27""##), 11);
+        assert_eq!(
+            encode(r##""\x27""##, Escaping::Puzzle)
+                .unwrap()
+                .chars()
+                .count(),
+            11
+        );
+    }
+
+    #[test]
+    fn errors_name_line() {
+        assert_eq!(
+            format!("{:#}", part1("\"ok\"\n\"bad\\q\"").unwrap_err()),
+            "line 2: unknown escape \\q at column 5"
+        );
+        assert_eq!(
+            format!("{:#}", part1("\"open").unwrap_err()),
+            "line 1: missing closing quote at column 6"
+        );
+    }
+
+    #[test]
+    fn only_quotes_and_backslashes() {
+        let line = "\"tab\there\u{e9}\"";
+        assert_eq!(part2(line, &Options::default()).unwrap().as_str(), "4");
+        let opts = Options::from_pairs(&[("escape", "bytes")]);
+        assert_eq!(part2(line, &opts).unwrap().as_str(), "10");
+        let opts = Options::from_pairs(&[("escape", "all")]);
+        assert!(part2(line, &opts).is_err());
     }
 
     #[test]
@@ -109,6 +120,6 @@ mod tests {
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "2074")
+        assert_eq!(part2(INPUT, &Options::default()).unwrap().as_str(), "2074")
     }
 }
@@ -0,0 +1,116 @@
+//! Held-Karp dynamic programming over subsets of cities
+//!
+//! `best[mask][last]` holds the best length of a path that visits exactly the
+//! cities in `mask` and ends at `last`. Every path is built by extending a
+//! smaller mask by one city, so the whole table is `O(2^n * n^2)` instead of
+//! the `O(n!)` of trying every permutation. A second table remembers which
+//! city each entry was reached from, so the winning path can be walked back.
+
+use anyhow::Result;
+
+use super::Distances;
+use crate::challenge::progress::Progress;
+
+/// Largest input the table can hold: `2^20 * 20` entries is 160MB of `u64`
+pub const MAX_LOCATIONS: usize = 20;
+
+/// Marks a `(mask, last)` pair that no path reaches
+///
+/// Lengths are `u64` so that even 19 roads of `u32::MAX` can't reach it.
+const UNREACHABLE: u64 = u64::MAX;
+/// Parent of a path's first city
+const NO_PARENT: u8 = u8::MAX;
+
+/// City ids in visiting order, along with the total length
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub order: Vec<usize>,
+    pub length: u64,
+}
+
+/// The shortest path that visits every city once
+pub fn shortest(ut: &Distances) -> Result<Option<Path>> {
+    best_path(ut, |a, b| a < b)
+}
+
+/// The longest path that visits every city once
+pub fn longest(ut: &Distances) -> Result<Option<Path>> {
+    best_path(ut, |a, b| a > b)
+}
+
+fn best_path<F>(ut: &Distances, better: F) -> Result<Option<Path>>
+where
+    F: Fn(u64, u64) -> bool,
+{
+    let n = ut.size();
+    if n == 0 {
+        return Ok(None);
+    }
+    if n > MAX_LOCATIONS {
+        anyhow::bail!(
+            "{} locations is more than the {} supported",
+            n,
+            MAX_LOCATIONS
+        );
+    }
+
+    let full = 1usize << n;
+    let progress = Progress::new("day9 subsets", Some(full as u64));
+    let mut best = vec![UNREACHABLE; full * n];
+    let mut parent = vec![NO_PARENT; full * n];
+    for start in 0..n {
+        best[(1 << start) * n + start] = 0;
+    }
+
+    for mask in 1..full {
+        progress.add(1)?;
+        for last in 0..n {
+            let so_far = best[mask * n + last];
+            if so_far == UNREACHABLE {
+                continue;
+            }
+            for next in 0..n {
+                if mask & (1 << next) != 0 {
+                    continue;
+                }
+                let d = match ut.get(last, next) {
+                    Some(road) => so_far + road as u64,
+                    None => continue,
+                };
+                let idx = (mask | 1 << next) * n + next;
+                if best[idx] == UNREACHABLE || better(d, best[idx]) {
+                    best[idx] = d;
+                    parent[idx] = last as u8;
+                }
+            }
+        }
+    }
+    progress.finish();
+
+    let ends = &best[(full - 1) * n..];
+    let end = (0..n)
+        .filter(|c| ends[*c] != UNREACHABLE)
+        .fold(None, |acc: Option<usize>, c| match acc {
+            Some(b) if !better(ends[c], ends[b]) => Some(b),
+            _ => Some(c),
+        });
+    let end = match end {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+
+    let mut order = vec![end];
+    let mut mask = full - 1;
+    let mut city = end;
+    while parent[mask * n + city] != NO_PARENT {
+        let prev = parent[mask * n + city] as usize;
+        mask ^= 1 << city;
+        city = prev;
+        order.push(city);
+    }
+    order.reverse();
+    Ok(Some(Path {
+        order,
+        length: ends[end],
+    }))
+}
@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
 
 use anyhow::{Context, Result};
-use itertools::Itertools;
-use upper_triangular::UpperTriangular;
+
+use super::{
+    graph::{Interner, UpperTriangular},
+    Options,
+};
+
+mod held_karp;
 
 /*
     L   D   B
@@ -11,72 +16,6 @@ D   464 0   141
 B   518 141 0
 */
 
-mod upper_triangular {
-    /// Store only the upper triangular portion of the matrix
-    ///
-    /// Assume any query for the lower triangle is the transpose
-    /// Assume anything along the center diagonal is the default value.
-    #[derive(Debug)]
-    pub struct UpperTriangular<T> {
-        inner: Vec<T>,
-        size: usize,
-    }
-
-    #[inline]
-    fn sum_to_n(x: usize) -> usize {
-        x * (x + 1) / 2
-    }
-
-    impl<T: Default + Clone> UpperTriangular<T> {
-        pub fn new(size: usize) -> UpperTriangular<T> {
-            let storage = sum_to_n(size - 1);
-            UpperTriangular {
-                inner: vec![T::default(); storage],
-                size,
-            }
-        }
-
-        pub fn size(&self) -> usize {
-            self.size
-        }
-
-        #[inline]
-        fn idx(&self, x: usize, y: usize) -> usize {
-            if x == y {
-                panic!("don't ask when x==y, these values do not appear");
-            }
-            let (x, y) = if x < y { (y, x) } else { (x, y) };
-
-            let idx_2d = y * self.size + x;
-            let skipped = sum_to_n(y + 1);
-            let idx = idx_2d - skipped;
-            log::trace!(
-                "X:{} Y:{} idx:{} skipped:{} => {}",
-                x,
-                y,
-                idx_2d,
-                skipped,
-                idx
-            );
-
-            idx
-        }
-
-        pub fn get(&self, x: usize, y: usize) -> T {
-            if x == y {
-                return T::default();
-            }
-            let idx = self.idx(x, y);
-            self.inner[idx].clone()
-        }
-
-        pub fn set(&mut self, x: usize, y: usize, value: T) {
-            let idx = self.idx(x, y);
-            self.inner[idx] = value
-        }
-    }
-}
-
 fn parse_line(input: &str) -> Result<(&str, &str, u32)> {
     let mut segments = input.split('=');
     let src_dst = segments
@@ -104,70 +43,314 @@ fn parse_line(input: &str) -> Result<(&str, &str, u32)> {
     Ok((src, dst, distance))
 }
 
-fn parse_input(input: &str) -> Result<UpperTriangular<u32>> {
-    let mut loc_map = HashMap::new();
-    let mut loc_to_id = |loc: &str| {
-        let seen = loc_map.len();
-        *loc_map.entry(loc.to_string()).or_insert(seen)
-    };
+/// Known road lengths, `None` where there is no road between two cities
+type Distances = UpperTriangular<Option<u32>>;
+
+/// Location names, indexed by the ids used in the distance matrix
+#[derive(Debug)]
+struct CityMap {
+    names: Interner,
+    distances: Distances,
+}
 
-    let mut buf = Vec::new();
+/// What to do about a pair of cities the input never mentions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissingEdges {
+    /// Every pair must be listed
+    Error,
+    /// Routes may not travel directly between unlisted pairs
+    Impassable,
+}
 
-    for line in input.lines() {
-        let (src, dst, dist) =
-            parse_line(line).with_context(|| format!("unable to parse line: {:?}", line))?;
+impl FromStr for MissingEdges {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "error" => Ok(MissingEdges::Error),
+            "impassable" => Ok(MissingEdges::Impassable),
+            _ => anyhow::bail!("expected `error` or `impassable`"),
+        }
+    }
+}
+
+fn parse_input(input: &str, missing: MissingEdges) -> Result<CityMap> {
+    let mut names = Interner::default();
+    // (lower id, higher id) => (distance, line number)
+    let mut edges = HashMap::new();
+
+    for (idx, line) in input.lines().enumerate() {
+        let line_no = idx + 1;
+        let (src, dst, dist) = parse_line(line)
+            .with_context(|| format!("unable to parse line {}: {:?}", line_no, line))?;
         log::trace!("src: {} dst: {} dist: {}", src, dst, dist);
-        buf.push((loc_to_id(src), loc_to_id(dst), dist))
+        if src == dst {
+            anyhow::bail!("line {}: {:?} has a distance to itself", line_no, src);
+        }
+        let (x, y) = (names.intern(src), names.intern(dst));
+        let key = (std::cmp::min(x, y), std::cmp::max(x, y));
+        if let Some((prev, prev_line)) = edges.insert(key, (dist, line_no)) {
+            if prev != dist {
+                anyhow::bail!(
+                    "line {}: {} to {} = {} conflicts with {} on line {}",
+                    line_no,
+                    src,
+                    dst,
+                    dist,
+                    prev,
+                    prev_line
+                );
+            }
+        }
     }
-    let mut ut = UpperTriangular::new(loc_map.len());
-    log::trace!("{:#?}", loc_map);
-    for (x, y, v) in buf {
-        ut.set(x, y, v);
+    log::trace!("{:#?}", names);
+
+    let mut ut = UpperTriangular::new(names.len());
+    for ((x, y), (v, _)) in &edges {
+        ut.set(*x, *y, Some(*v));
+    }
+
+    if missing == MissingEdges::Error {
+        let mut unlisted = Vec::new();
+        for x in 0..names.len() {
+            for y in x + 1..names.len() {
+                if ut.get(x, y).is_none() {
+                    unlisted.push(format!("{} to {}", names.name(x), names.name(y)));
+                }
+            }
+        }
+        if !unlisted.is_empty() {
+            anyhow::bail!("missing distances for: {}", unlisted.join(", "));
+        }
     }
-    Ok(ut)
-}
 
-fn permute_distances(ut: &UpperTriangular<u32>) -> impl Iterator<Item = u32> + '_ {
-    (0..ut.size()).permutations(ut.size()).map(move |p| {
-        p.iter()
-            .tuple_windows()
-            .map(|(s, e)| ut.get(*s, *e))
-            .sum::<u32>()
+    Ok(CityMap {
+        names,
+        distances: ut,
     })
 }
 
-pub fn part1(input: &str) -> Result<String> {
-    let ut = parse_input(input).context("unable to parse input")?;
+const KNOWN_ROADS: &str = "routes only travel on known roads";
 
-    let path = permute_distances(&ut)
-        .min()
-        .ok_or_else(|| anyhow::anyhow!("there were no possible paths in input"))?;
+/// One step of a route
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub from: String,
+    pub to: String,
+    pub distance: u32,
+}
 
-    Ok(format!("{:?}", path))
+/// A complete route through every location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub stops: Vec<String>,
+    pub legs: Vec<Leg>,
+    pub distance: u64,
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let ut = parse_input(input).context("unable to parse input")?;
+impl Route {
+    fn from_path(map: &CityMap, path: held_karp::Path) -> Route {
+        let stops = path
+            .order
+            .iter()
+            .map(|id| map.names.name(*id).to_string())
+            .collect();
+        let legs = path
+            .order
+            .windows(2)
+            .map(|w| Leg {
+                from: map.names.name(w[0]).to_string(),
+                to: map.names.name(w[1]).to_string(),
+                distance: map.distances.get(w[0], w[1]).expect(KNOWN_ROADS),
+            })
+            .collect();
+        Route {
+            stops,
+            legs,
+            distance: path.length,
+        }
+    }
+}
 
-    let path = permute_distances(&ut)
-        .max()
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // No trailing newline, so the route can go in the middle of a log line
+        write!(f, "{} = {}", self.stops.join(" -> "), self.distance)?;
+        for leg in &self.legs {
+            write!(f, "\n  {} to {} = {}", leg.from, leg.to, leg.distance)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn shortest_route(input: &str, missing: MissingEdges) -> Result<Route> {
+    let map = parse_input(input, missing).context("unable to parse input")?;
+    let path = held_karp::shortest(&map.distances)?
         .ok_or_else(|| anyhow::anyhow!("there were no possible paths in input"))?;
+    Ok(Route::from_path(&map, path))
+}
 
-    Ok(format!("{:?}", path))
+pub fn longest_route(input: &str, missing: MissingEdges) -> Result<Route> {
+    let map = parse_input(input, missing).context("unable to parse input")?;
+    let path = held_karp::longest(&map.distances)?
+        .ok_or_else(|| anyhow::anyhow!("there were no possible paths in input"))?;
+    Ok(Route::from_path(&map, path))
+}
+
+/// Options: `missing=error|impassable` (default `error`)
+fn missing_edges(opts: &Options) -> Result<MissingEdges> {
+    Ok(opts.get("missing")?.unwrap_or(MissingEdges::Error))
+}
+
+pub fn part1(input: &str, opts: &Options) -> Result<String> {
+    let route = shortest_route(input, missing_edges(opts)?)?;
+    log::info!("shortest route: {}", route);
+    Ok(format!("{:?}", route.distance))
+}
+
+pub fn part2(input: &str, opts: &Options) -> Result<String> {
+    let route = longest_route(input, missing_edges(opts)?)?;
+    log::info!("longest route: {}", route);
+    Ok(format!("{:?}", route.distance))
 }
 
 #[cfg(test)]
 mod tests {
+    use itertools::Itertools;
+
     use super::*;
+    use crate::challenge::graph;
     const INPUT: &str = include_str!("../../../input/day9");
+    const EX: &str = include_str!("../../../input/day9_ex");
+
+    fn permute_distances(ut: &Distances) -> impl Iterator<Item = u64> + '_ {
+        (0..ut.size())
+            .permutations(ut.size())
+            .filter_map(move |p| graph::path_weight(ut, &p))
+            .map(u64::from)
+    }
+
+    fn assert_matches_permutations(ut: &Distances) {
+        let shortest = held_karp::shortest(ut).unwrap().unwrap();
+        let longest = held_karp::longest(ut).unwrap().unwrap();
+        assert_eq!(Some(shortest.length), permute_distances(ut).min());
+        assert_eq!(Some(longest.length), permute_distances(ut).max());
+        for path in &[shortest, longest] {
+            let length = path
+                .order
+                .windows(2)
+                .map(|w| ut.get(w[0], w[1]).unwrap() as u64);
+            assert_eq!(length.sum::<u64>(), path.length);
+            assert_eq!(
+                path.order.iter().sorted().cloned().collect::<Vec<_>>(),
+                (0..ut.size()).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn held_karp_matches_permutations() {
+        assert_matches_permutations(&parse_input(EX, MissingEdges::Error).unwrap().distances);
+        assert_matches_permutations(&parse_input(INPUT, MissingEdges::Error).unwrap().distances);
+        let mut two = UpperTriangular::new(2);
+        two.set(0, 1, Some(7));
+        assert_matches_permutations(&two);
+    }
+
+    #[test]
+    fn check_example() {
+        assert_eq!(part1(EX, &Options::default()).unwrap().as_str(), "605");
+        assert_eq!(part2(EX, &Options::default()).unwrap().as_str(), "982");
+    }
+
+    #[test]
+    fn example_route() {
+        let route = shortest_route(EX, MissingEdges::Error).unwrap();
+        assert_eq!(route.distance, 605);
+        // either direction is equally short
+        let mut stops = route.stops.clone();
+        if stops[0] != "London" {
+            stops.reverse();
+        }
+        assert_eq!(stops, vec!["London", "Dublin", "Belfast"]);
+        assert_eq!(
+            route.legs.iter().map(|l| l.distance as u64).sum::<u64>(),
+            route.distance
+        );
+        let shown = route.to_string();
+        assert_eq!(shown.lines().count(), 3);
+        assert!(!shown.ends_with('\n'));
+    }
+
+    #[test]
+    fn self_loop() {
+        let err = parse_input("A to A = 3", MissingEdges::Error).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: \"A\" has a distance to itself");
+    }
+
+    #[test]
+    fn conflicting_duplicate() {
+        let input = "A to B = 3\nA to C = 4\nB to C = 5\nB to A = 7";
+        let err = parse_input(input, MissingEdges::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4: B to A = 7 conflicts with 3 on line 1"
+        );
+
+        let input = "A to B = 3\nA to C = 4\nB to C = 5\nB to A = 3";
+        assert!(parse_input(input, MissingEdges::Error).is_ok());
+    }
+
+    #[test]
+    fn missing_edges() {
+        let input = "A to B = 3\nB to C = 5\nC to D = 1";
+        let err = parse_input(input, MissingEdges::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing distances for: A to C, A to D, B to D"
+        );
+
+        // the only route is the chain A-B-C-D
+        let route = longest_route(input, MissingEdges::Impassable).unwrap();
+        assert_eq!(route.distance, 9);
+        let route = shortest_route(input, MissingEdges::Impassable).unwrap();
+        assert_eq!(route.distance, 9);
+    }
+
+    #[test]
+    fn impassable_matches_permutations() {
+        let mut ut = parse_input(INPUT, MissingEdges::Error).unwrap().distances;
+        ut.set(0, 3, None);
+        ut.set(2, 5, None);
+        ut.set(1, 6, None);
+        assert_matches_permutations(&ut);
+    }
+
+    #[test]
+    fn huge_distances() {
+        let input = "A to B = 4294967295\nB to C = 4294967295\nA to C = 1";
+        assert_eq!(
+            part2(input, &Options::default()).unwrap().as_str(),
+            "8589934590"
+        );
+        assert_eq!(
+            part1(input, &Options::default()).unwrap().as_str(),
+            "4294967296"
+        );
+    }
+
+    #[test]
+    fn disconnected() {
+        let input = "A to B = 3\nC to D = 5";
+        assert!(shortest_route(input, MissingEdges::Impassable).is_err());
+    }
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "251")
+        assert_eq!(part1(INPUT, &Options::default()).unwrap().as_str(), "251")
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "898")
+        assert_eq!(part2(INPUT, &Options::default()).unwrap().as_str(), "898")
     }
 
     #[test]
@@ -0,0 +1,52 @@
+use super::{upper_triangular::UpperTriangular, Weights};
+
+/// Dense `size * size` matrix, for weights that differ by direction
+#[derive(Debug)]
+pub struct Matrix<T> {
+    inner: Vec<T>,
+    size: usize,
+}
+
+impl<T: Default + Clone> Matrix<T> {
+    pub fn new(size: usize) -> Matrix<T> {
+        Matrix {
+            inner: vec![T::default(); size * size],
+            size,
+        }
+    }
+
+    #[inline]
+    fn idx(&self, x: usize, y: usize) -> usize {
+        self.size * y + x
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> T {
+        let idx = self.idx(x, y);
+        self.inner[idx].clone()
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        let idx = self.idx(x, y);
+        self.inner[idx] = value
+    }
+}
+
+impl<T: Default + Clone + std::ops::Add<Output = T>> Matrix<T> {
+    /// Combine both directions of every pair: `m[x][y] + m[y][x]`
+    pub fn symmetric_sum(&self) -> UpperTriangular<T> {
+        let mut ut = UpperTriangular::new(self.size);
+        for x in 0..self.size {
+            for y in x + 1..self.size {
+                ut.set(x, y, self.get(x, y) + self.get(y, x));
+            }
+        }
+        ut
+    }
+}
+
+impl<T: Default + Copy + std::iter::Sum> Weights for Matrix<T> {
+    type Weight = T;
+    fn weight(&self, from: usize, to: usize) -> T {
+        self.get(from, to)
+    }
+}
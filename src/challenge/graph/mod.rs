@@ -0,0 +1,82 @@
+//! Named locations with weighted edges between them
+//!
+//! Inputs like "A to B = 5" or "A would gain 5 ... next to B" are interned into
+//! dense ids, and the weights stored in either a symmetric [`UpperTriangular`]
+//! or a directional [`Matrix`].
+
+mod interner;
+mod matrix;
+mod upper_triangular;
+
+pub use interner::Interner;
+pub use matrix::Matrix;
+pub use upper_triangular::UpperTriangular;
+
+/// Weight of travelling directly between two ids
+pub trait Weights {
+    type Weight: Copy + std::iter::Sum;
+    fn weight(&self, from: usize, to: usize) -> Self::Weight;
+}
+
+/// Total weight of visiting `order` from first to last
+pub fn path_weight<W: Weights>(w: &W, order: &[usize]) -> W::Weight {
+    order.windows(2).map(|e| w.weight(e[0], e[1])).sum()
+}
+
+/// Total weight of visiting `order` and returning to the start
+pub fn cycle_weight<W: Weights>(w: &W, order: &[usize]) -> W::Weight {
+    let closing = match (order.first(), order.last()) {
+        (Some(first), Some(last)) if order.len() > 1 => Some(w.weight(*last, *first)),
+        _ => None,
+    };
+    order
+        .windows(2)
+        .map(|e| w.weight(e[0], e[1]))
+        .chain(closing)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_names() {
+        let mut names = Interner::default();
+        assert_eq!(names.intern("London"), 0);
+        assert_eq!(names.intern("Dublin"), 1);
+        assert_eq!(names.intern("London"), 0);
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.name(1), "Dublin");
+        assert_eq!(names.id("Dublin"), Some(1));
+        assert_eq!(names.id("Belfast"), None);
+    }
+
+    #[test]
+    fn symmetric_weights() {
+        let mut ut = UpperTriangular::new(3);
+        ut.set(0, 1, 464);
+        ut.set(0, 2, 518);
+        ut.set(2, 1, 141);
+        assert_eq!(ut.weight(1, 2), 141);
+        assert_eq!(path_weight(&ut, &[0, 1, 2]), 605);
+        assert_eq!(cycle_weight(&ut, &[0, 1, 2]), 605 + 518);
+        assert_eq!(cycle_weight(&ut, &[1]), 0);
+    }
+
+    #[test]
+    fn directional_weights() {
+        let mut m = Matrix::new(3);
+        m.set(0, 1, 54);
+        m.set(1, 0, 83);
+        m.set(1, 2, -7);
+        m.set(2, 1, 60);
+        assert_eq!(path_weight(&m, &[0, 1, 2]), 47);
+        assert_eq!(path_weight(&m, &[2, 1, 0]), 143);
+
+        let pairs = m.symmetric_sum();
+        assert_eq!(pairs.get(1, 0), 137);
+        assert_eq!(pairs.get(2, 1), 53);
+        assert_eq!(cycle_weight(&pairs, &[0, 1, 2]), 190);
+    }
+}
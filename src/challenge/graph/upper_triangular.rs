@@ -0,0 +1,72 @@
+use super::Weights;
+
+/// Store only the upper triangular portion of the matrix
+///
+/// Assume any query for the lower triangle is the transpose
+/// Assume anything along the center diagonal is the default value.
+#[derive(Debug)]
+pub struct UpperTriangular<T> {
+    inner: Vec<T>,
+    size: usize,
+}
+
+#[inline]
+fn sum_to_n(x: usize) -> usize {
+    x * (x + 1) / 2
+}
+
+impl<T: Default + Clone> UpperTriangular<T> {
+    pub fn new(size: usize) -> UpperTriangular<T> {
+        let storage = sum_to_n(size.saturating_sub(1));
+        UpperTriangular {
+            inner: vec![T::default(); storage],
+            size,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn idx(&self, x: usize, y: usize) -> usize {
+        if x == y {
+            panic!("don't ask when x==y, these values do not appear");
+        }
+        let (x, y) = if x < y { (y, x) } else { (x, y) };
+
+        let idx_2d = y * self.size + x;
+        let skipped = sum_to_n(y + 1);
+        let idx = idx_2d - skipped;
+        log::trace!(
+            "X:{} Y:{} idx:{} skipped:{} => {}",
+            x,
+            y,
+            idx_2d,
+            skipped,
+            idx
+        );
+
+        idx
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> T {
+        if x == y {
+            return T::default();
+        }
+        let idx = self.idx(x, y);
+        self.inner[idx].clone()
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        let idx = self.idx(x, y);
+        self.inner[idx] = value
+    }
+}
+
+impl<T: Default + Copy + std::iter::Sum> Weights for UpperTriangular<T> {
+    type Weight = T;
+    fn weight(&self, from: usize, to: usize) -> T {
+        self.get(from, to)
+    }
+}
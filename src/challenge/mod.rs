@@ -19,48 +19,78 @@ mod day13;
 mod day14;
 mod day15;
 
+mod chars;
+mod graph;
+mod options;
+mod progress;
+mod sentence;
+use options::Options;
+
 pub fn run(args: &clap::ArgMatches) -> Result<()> {
     let day = args.value_of("day").unwrap().parse::<u32>()?;
-    let part = args.value_of("part").unwrap().parse::<u32>()?;
+    let part = args.value_of("part").unwrap().to_string();
     let input = read_to_string(args.value_of("input").unwrap())?;
+    let opts = Options::from_args(args)?;
+    let timeout = args
+        .value_of("timeout")
+        .map(progress::parse_timeout)
+        .transpose()?;
     log::debug!("running day {}:{}", day, part);
-    let result = match (day, part) {
-        (1, 1) => day1::part1(&input),
-        (1, 2) => day1::part2(&input),
-        (2, 1) => day2::part1(&input),
-        (2, 2) => day2::part2(&input),
-        (3, 1) => day3::part1(&input),
-        (3, 2) => day3::part2(&input),
-        (4, 1) => day4::part1(&input),
-        (4, 2) => day4::part2(&input),
-        (5, 1) => day5::part1(&input),
-        (5, 2) => day5::part2(&input),
-        (6, 1) => day6::part1(&input),
-        (6, 2) => day6::part2(&input),
-        (7, 1) => day7::part1(&input),
-        (7, 2) => day7::part2(&input),
-        (8, 1) => day8::part1(&input),
-        (8, 2) => day8::part2(&input),
-        (9, 1) => day9::part1(&input),
-        (9, 2) => day9::part2(&input),
-        (10, 1) => day10::part1(&input),
-        (10, 2) => day10::part2(&input),
-        (11, 1) => day11::part1(&input),
-        (11, 2) => day11::part2(&input),
-        (12, 1) => day12::part1(&input),
-        (12, 2) => day12::part2(&input),
-        (13, 1) => day13::part1(&input),
-        (13, 2) => day13::part2(&input),
-        (14, 1) => day14::part1(&input),
-        (14, 2) => day14::part2(&input),
-        (15, 1) => day15::part1(&input),
-        (15, 2) => day15::part2(&input),
-        (d, p) => Err(ah!("unimplemented challenge day {} part {}", d, p)),
+    let result = match timeout {
+        Some(t) => progress::with_timeout(t, move || solve(day, &part, &input, &opts)),
+        None => solve(day, &part, &input, &opts),
     }?;
     println!("{}", result);
     Ok(())
 }
 
+fn solve(day: u32, part: &str, input: &str, opts: &Options) -> Result<String> {
+    match (day, part) {
+        (1, "1") => day1::part1(input, opts),
+        (1, "2") => day1::part2(input, opts),
+        (1, "trace") => day1::trace(input, opts),
+        (2, "1") => day2::part1(input),
+        (2, "2") => day2::part2(input),
+        (2, "cost") => day2::cost(input, opts),
+        (2, "report") => day2::report(input, opts),
+        (3, "1") => day3::part1(input, opts),
+        (3, "2") => day3::part2(input, opts),
+        (3, "agents") => day3::agents(input, opts),
+        (3, "map") => day3::map(input, opts),
+        (4, "1") => day4::part1(input),
+        (4, "2") => day4::part2(input),
+        (4, "mine") => day4::mine(input, opts),
+        (5, "1") => day5::part1(input),
+        (5, "2") => day5::part2(input),
+        (5, "check") => day5::check(input, opts),
+        (5, "explain") => day5::explain(input, opts),
+        (6, "1") => day6::part1(input),
+        (6, "2") => day6::part2(input),
+        (7, "1") => day7::part1(input),
+        (7, "2") => day7::part2(input),
+        (8, "1") => day8::part1(input),
+        (8, "2") => day8::part2(input, opts),
+        (9, "1") => day9::part1(input, opts),
+        (9, "2") => day9::part2(input, opts),
+        (10, "1") => day10::part1(input, opts),
+        (10, "2") => day10::part2(input, opts),
+        (11, "1") => day11::part1(input, opts),
+        (11, "2") => day11::part2(input, opts),
+        (12, "1") => day12::part1(input),
+        (12, "2") => day12::part2(input),
+        (13, "1") => day13::part1(input),
+        (13, "2") => day13::part2(input),
+        (13, "seating") => day13::seating(input, opts),
+        (14, "1") => day14::part1(input, opts),
+        (14, "2") => day14::part2(input, opts),
+        (14, "score") => day14::standings(input, opts),
+        (14, "race") => day14::race(input, opts),
+        (15, "1") => day15::part1(input),
+        (15, "2") => day15::part2(input),
+        (d, p) => Err(ah!("unimplemented challenge day {} part {}", d, p)),
+    }
+}
+
 fn read_to_string<P: AsRef<path::Path>>(path: P) -> Result<String> {
     log::trace!("Reading content of file: {}", path.as_ref().display());
     let mut f = fs::File::open(&path)
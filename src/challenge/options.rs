@@ -0,0 +1,105 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use anyhow::{anyhow as ah, Context, Result};
+
+/// Extra `KEY=VALUE` settings passed on the command line with `-o`
+///
+/// Challenges look up the keys they understand and ignore the rest.
+#[derive(Debug, Default)]
+pub struct Options {
+    inner: HashMap<String, String>,
+}
+
+impl Options {
+    pub fn from_args(args: &clap::ArgMatches) -> Result<Options> {
+        let mut inner = HashMap::new();
+        for opt in args.values_of("option").into_iter().flatten() {
+            let (key, value) = opt
+                .split_once('=')
+                .ok_or_else(|| ah!("option was not in KEY=VALUE form: {:?}", opt))?;
+            inner.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        log::trace!("{:?}", inner);
+        Ok(Options { inner })
+    }
+
+    /// Build options directly, as if each pair had been passed with `-o`
+    #[cfg(test)]
+    pub fn from_pairs(pairs: &[(&str, &str)]) -> Options {
+        Options {
+            inner: pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.inner.get(key).map(|s| s.as_str())
+    }
+
+    /// Report format from `format=text|json|csv`, defaulting to text
+    pub fn format(&self) -> Result<Format> {
+        Ok(self.get("format")?.unwrap_or(Format::Text))
+    }
+
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.get_str(key)
+            .map(|s| {
+                s.parse::<T>()
+                    .map_err(|e| ah!("{}", e))
+                    .with_context(|| format!("invalid value for option {:?}: {:?}", key, s))
+            })
+            .transpose()
+    }
+}
+
+/// How a report should be written out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => anyhow::bail!("unknown format, expected `text`, `json` or `csv`"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(pairs: &[(&str, &str)]) -> Options {
+        Options::from_pairs(pairs)
+    }
+
+    #[test]
+    fn typed_lookup() {
+        let o = opts(&[("zeros", "6"), ("prefix", "abc")]);
+        assert_eq!(o.get::<u32>("zeros").unwrap(), Some(6));
+        assert_eq!(o.get::<u32>("start").unwrap(), None);
+        assert_eq!(o.get_str("prefix"), Some("abc"));
+        assert!(o.get::<u32>("prefix").is_err());
+    }
+
+    #[test]
+    fn report_format() {
+        assert_eq!(opts(&[]).format().unwrap(), Format::Text);
+        assert_eq!(opts(&[("format", "json")]).format().unwrap(), Format::Json);
+        assert_eq!(opts(&[("format", "csv")]).format().unwrap(), Format::Csv);
+        assert!(opts(&[("format", "xml")]).format().is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow as ah, Result};
+
+/// Only look at the clock once this many candidates have gone by
+const REPORT_STRIDE: u64 = 1024;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a cancelled run gets to notice and return its own error
+const CANCEL_GRACE: Duration = Duration::from_millis(250);
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Ask every running search to stop at its next progress update
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::Relaxed)
+}
+
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::Relaxed)
+}
+
+/// A `--timeout` in seconds, which may be fractional but not negative
+pub fn parse_timeout(secs: &str) -> Result<Duration> {
+    let secs = secs
+        .parse::<f64>()
+        .map_err(|_| ah!("timeout must be a number of seconds, found {:?}", secs))?;
+    Duration::try_from_secs_f64(secs).map_err(|_| {
+        ah!(
+            "timeout must be a finite, non-negative number of seconds, found {}",
+            secs
+        )
+    })
+}
+
+/// Run `f` on its own thread, cancelling it if it takes longer than `timeout`
+pub fn with_timeout<T, F>(timeout: Duration, f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(r) => r,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            cancel();
+            let err = ah!("timed out after {:?}", timeout);
+            match rx.recv_timeout(CANCEL_GRACE) {
+                Ok(Err(e)) => Err(e.context(err)),
+                _ => Err(err),
+            }
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(ah!("challenge thread exited early")),
+    }
+}
+
+/// Candidate counter for a long search, shared between threads
+///
+/// Rate and ETA are logged at `info` level (`-v`) about once a second.
+#[derive(Debug)]
+pub struct Progress {
+    label: &'static str,
+    total: Option<u64>,
+    done: AtomicU64,
+    start: Instant,
+    /// Milliseconds after `start` when the next report is due
+    next_report: AtomicU64,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: Option<u64>) -> Progress {
+        Progress {
+            label,
+            total,
+            done: AtomicU64::new(0),
+            start: Instant::now(),
+            next_report: AtomicU64::new(REPORT_INTERVAL.as_millis() as u64),
+        }
+    }
+
+    /// Record `n` more candidates, failing if the run has been cancelled
+    pub fn add(&self, n: u64) -> Result<()> {
+        let before = self.done.fetch_add(n, Ordering::Relaxed);
+        let after = before + n;
+        self.check()?;
+        if before / REPORT_STRIDE != after / REPORT_STRIDE {
+            self.maybe_report(after);
+        }
+        Ok(())
+    }
+
+    /// Fail if the run has been cancelled, for work that isn't a whole candidate
+    pub fn check(&self) -> Result<()> {
+        if is_cancelled() {
+            return Err(ah!(
+                "{} cancelled after {} candidates",
+                self.label,
+                self.done()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn done(&self) -> u64 {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Log the final count and rate
+    pub fn finish(&self) {
+        let done = self.done();
+        let elapsed = self.start.elapsed();
+        log::debug!(
+            "{}: {} candidates in {:?} ({:.0}/s)",
+            self.label,
+            done,
+            elapsed,
+            done as f64 / elapsed.as_secs_f64()
+        );
+    }
+
+    fn maybe_report(&self, done: u64) {
+        let elapsed = self.start.elapsed();
+        let now_ms = elapsed.as_millis() as u64;
+        let due = self.next_report.load(Ordering::Relaxed);
+        if now_ms < due {
+            return;
+        }
+        let next = now_ms + REPORT_INTERVAL.as_millis() as u64;
+        if self
+            .next_report
+            .compare_exchange(due, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            // another thread just reported
+            return;
+        }
+        let rate = done as f64 / elapsed.as_secs_f64();
+        match self.total {
+            Some(total) => {
+                let remaining = total.saturating_sub(done) as f64 / rate;
+                log::info!(
+                    "{}: {}/{} ({:.1}%) {:.0}/s eta {:.1}s",
+                    self.label,
+                    done,
+                    total,
+                    100.0 * done as f64 / total as f64,
+                    rate,
+                    remaining
+                );
+            }
+            None => log::info!("{}: {} {:.0}/s", self.label, done, rate),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_across_threads() {
+        let p = Progress::new("test", Some(4000));
+        rayon::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|_| {
+                    for _ in 0..1000 {
+                        p.add(1).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(p.done(), 4000);
+    }
+
+    #[test]
+    fn timeouts() {
+        assert_eq!(parse_timeout("1.5").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_timeout("0").unwrap(), Duration::ZERO);
+        assert_eq!(
+            parse_timeout("soon").unwrap_err().to_string(),
+            "timeout must be a number of seconds, found \"soon\""
+        );
+        for bad in &["nan", "-1", "inf", "1e300"] {
+            assert!(parse_timeout(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            parse_timeout("-1").unwrap_err().to_string(),
+            "timeout must be a finite, non-negative number of seconds, found -1"
+        );
+    }
+}
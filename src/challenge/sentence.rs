@@ -0,0 +1,117 @@
+//! Reading fixed-form input sentences one field at a time
+//!
+//! A [`Cursor`] walks a line from left to right, so every error can say which
+//! field was wrong and the column where it started.
+
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow as ah, Result};
+
+/// What is left of a line, and where it starts
+pub struct Cursor<'a> {
+    rest: &'a str,
+    col: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(line: &'a str) -> Cursor<'a> {
+        Cursor { rest: line, col: 1 }
+    }
+
+    /// Column of the next character, counting from 1
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    fn advance(&mut self, n: usize) -> &'a str {
+        let (taken, rest) = self.rest.split_at(n);
+        self.rest = rest;
+        self.col += taken.chars().count();
+        taken
+    }
+
+    /// A run of letters and digits
+    pub fn word(&mut self, field: &str) -> Result<&'a str> {
+        let len = self
+            .rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err(ah!(
+                "missing {} at column {}, found {:?}",
+                field,
+                self.col,
+                self.rest
+            ));
+        }
+        Ok(self.advance(len))
+    }
+
+    /// A word that has to parse as a number
+    pub fn number<T>(&mut self, field: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let col = self.col;
+        let word = self.word(field)?;
+        word.parse::<T>().map_err(|e| {
+            ah!(
+                "{} {:?} at column {} is not a number: {}",
+                field,
+                word,
+                col,
+                e
+            )
+        })
+    }
+
+    pub fn literal(&mut self, expected: &str, after: &str) -> Result<()> {
+        if !self.rest.starts_with(expected) {
+            return Err(ah!(
+                "expected {:?} after {} at column {}, found {:?}",
+                expected,
+                after,
+                self.col,
+                self.rest
+            ));
+        }
+        self.advance(expected.len());
+        Ok(())
+    }
+
+    pub fn end(&self) -> Result<()> {
+        if !self.rest.is_empty() {
+            return Err(ah!(
+                "unexpected trailing text at column {}: {:?}",
+                self.col,
+                self.rest
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_in_order() {
+        let mut c = Cursor::new("Comet ran 14 km.");
+        assert_eq!(c.word("name").unwrap(), "Comet");
+        c.literal(" ran ", "name").unwrap();
+        assert_eq!(c.col(), 11);
+        assert_eq!(c.number::<u32>("distance").unwrap(), 14);
+        c.literal(" km.", "distance").unwrap();
+        c.end().unwrap();
+
+        let mut c = Cursor::new("Comet ran far");
+        c.word("name").unwrap();
+        c.literal(" ran ", "name").unwrap();
+        assert_eq!(
+            c.number::<u32>("distance").unwrap_err().to_string(),
+            "distance \"far\" at column 11 is not a number: invalid digit found in string"
+        );
+    }
+}
@@ -64,5 +64,20 @@ fn get_args() -> clap::ArgMatches<'static> {
         .arg(clap::Arg::with_name("day").required(true))
         .arg(clap::Arg::with_name("part").required(true))
         .arg(clap::Arg::with_name("input").required(true))
+        .arg(
+            clap::Arg::with_name("option")
+                .short("o")
+                .long("option")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Extra KEY=VALUE setting for the challenge"),
+        )
+        .arg(
+            clap::Arg::with_name("timeout")
+                .long("timeout")
+                .takes_value(true)
+                .help("Give up after this many seconds"),
+        )
         .get_matches()
 }